@@ -1,6 +1,6 @@
 //! Test binds
 
-#[mock::app(parse_binds,
+#[mock::app(parse_binds, allow(unused_dispatcher),
     dispatchers = [
         #[link_section = ".data.UART1"]
         A,
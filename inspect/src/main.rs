@@ -0,0 +1,757 @@
+//! Prints what `rtic-syntax` makes of an RTIC application
+//!
+//! ``` text
+//! rtic-inspect [OPTIONS] FILE
+//! ```
+//!
+//! `FILE` is a Rust source file that contains a module annotated with `#[app(..)]` or
+//! `#[rtic::app(..)]`. The module is parsed and analyzed with the `Settings` selected through
+//! `OPTIONS` and a report of the tasks, resources and channels is printed.
+
+#![deny(rust_2018_compatibility)]
+#![deny(rust_2018_idioms)]
+
+use std::{env, fmt::Write as _, fs, process};
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use rtic_syntax::{
+    analyze::{Analysis, Core, Handler, Location, Ownership},
+    ast::{Access, App, Binding},
+    cfg::{Cfg, CfgSet},
+    device::Device,
+    export::Document,
+    Settings,
+};
+use syn::{Attribute, Item, ItemMod};
+
+const USAGE: &str = "\
+Usage: rtic-inspect [OPTIONS] FILE
+
+Options:
+    --parse-binds             accept `binds` in `#[task]`
+    --parse-extern-interrupt  parse `extern` interrupts
+    --parse-async-tasks       accept `async` software tasks
+    --optimize-priorities     compress the task priorities
+    --device DEVICE           check `binds` and `dispatchers` against the interrupts and
+                              exceptions listed in DEVICE (text, or JSON if it ends in `.json`)
+    --cfg SPEC                only keep the items whose `#[cfg]` holds with SPEC set, e.g.
+                              `--cfg 'feature=\"radio\"'`; can be repeated
+    --features FEATURES       same as `--cfg 'feature=\"F\"'` for each comma separated F
+    --json                    print the report as JSON
+    --dot                     print the task / resource graph in the Graphviz DOT language
+    -h, --help                print this message";
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+struct Options {
+    settings: Settings,
+    json: bool,
+    dot: bool,
+    file: String,
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut settings = Settings::default();
+    let mut json = false;
+    let mut dot = false;
+    let mut file = None;
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--parse-binds" => settings.parse_binds = true,
+            "--parse-extern-interrupt" => settings.parse_extern_interrupt = true,
+            "--parse-async-tasks" => settings.parse_async_tasks = true,
+            "--optimize-priorities" => settings.optimize_priorities = true,
+            "--device" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| format!("error: `--device` needs a DEVICE\n\n{}", USAGE))?;
+                settings.device = Some(load_device(&path)?);
+            }
+            "--cfg" => {
+                let spec = args
+                    .next()
+                    .ok_or_else(|| format!("error: `--cfg` needs a SPEC\n\n{}", USAGE))?;
+                settings
+                    .cfg
+                    .get_or_insert_with(CfgSet::default)
+                    .insert_spec(&spec)
+                    .map_err(|e| format!("error: {}", e))?;
+            }
+            "--features" => {
+                let features = args
+                    .next()
+                    .ok_or_else(|| format!("error: `--features` needs FEATURES\n\n{}", USAGE))?;
+                let set = settings.cfg.get_or_insert_with(CfgSet::default);
+                for feature in features.split(',').filter(|feature| !feature.is_empty()) {
+                    set.insert_feature(feature.trim());
+                }
+            }
+            "--json" => json = true,
+            "--dot" => dot = true,
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if arg.starts_with('-') => {
+                return Err(format!("error: unknown option `{}`\n\n{}", arg, USAGE))
+            }
+            _ if file.is_some() => {
+                return Err(format!("error: more than one FILE given\n\n{}", USAGE))
+            }
+            _ => file = Some(arg),
+        }
+    }
+
+    Ok(Options {
+        settings,
+        json,
+        dot,
+        file: file.ok_or_else(|| format!("error: no FILE given\n\n{}", USAGE))?,
+    })
+}
+
+/// Loads the device description at `path`
+fn load_device(path: &str) -> Result<Device, String> {
+    let text =
+        fs::read_to_string(path).map_err(|e| format!("error: couldn't read `{}`: {}", path, e))?;
+
+    if path.ends_with(".json") {
+        Device::from_json(&text)
+    } else {
+        Device::parse(&text)
+    }
+    .map_err(|e| format!("error: `{}`: {}", path, e))
+}
+
+fn run() -> Result<(), String> {
+    let options = parse_options(env::args().skip(1))?;
+
+    let source = fs::read_to_string(&options.file)
+        .map_err(|e| format!("error: couldn't read `{}`: {}", options.file, e))?;
+    let file = syn::parse_file(&source).map_err(|e| render_error(&options.file, e))?;
+
+    let (args, input) = find_app(&file.items)
+        .ok_or_else(|| format!("error: no `#[app]` module found in `{}`", options.file))?;
+
+    let (app, analysis) = rtic_syntax::parse2(args, input, options.settings)
+        .map_err(|e| render_error(&options.file, e))?;
+
+    for warning in &analysis.warnings {
+        let start = warning.span.start();
+        eprintln!(
+            "{}:{}:{}: warning: {} [{}]",
+            options.file,
+            start.line,
+            start.column + 1,
+            warning.message,
+            warning.lint.name()
+        );
+    }
+
+    if options.dot {
+        print!("{}", analysis.to_dot(&app));
+    } else if options.json {
+        println!(
+            "{}",
+            Document::new(&app, &analysis, &options.file).to_json()
+        );
+    } else {
+        print!("{}", report(&app, &analysis));
+    }
+
+    Ok(())
+}
+
+/// Renders all the errors as `file:line:column: error: message`
+fn render_error(file: &str, error: syn::Error) -> String {
+    error
+        .into_iter()
+        .map(|e| {
+            let start = e.span().start();
+            format!("{}:{}:{}: error: {}", file, start.line, start.column + 1, e)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Is this the `#[app]` attribute? (also as `#[rtic::app]`, `#[mock::app]`, ..)
+fn is_app(attr: &Attribute) -> bool {
+    attr.path
+        .segments
+        .last()
+        .map(|segment| segment.ident == "app")
+        .unwrap_or(false)
+}
+
+/// Finds the `#[app]` module, returns the arguments of the attribute and the module
+fn find_app(items: &[Item]) -> Option<(TokenStream2, TokenStream2)> {
+    for item in items {
+        if let Item::Mod(module) = item {
+            if let Some(attr) = module.attrs.iter().find(|attr| is_app(attr)) {
+                let args = if attr.tokens.is_empty() {
+                    TokenStream2::new()
+                } else {
+                    attr.parse_args().ok()?
+                };
+
+                return Some((args, app_module(module)));
+            }
+
+            if let Some((_, items)) = &module.content {
+                if let Some(app) = find_app(items) {
+                    return Some(app);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// The module as `rtic-syntax` expects it: no attributes, no visibility
+fn app_module(module: &ItemMod) -> TokenStream2 {
+    let ident = &module.ident;
+    let items = module
+        .content
+        .as_ref()
+        .map(|(_, items)| &items[..])
+        .unwrap_or(&[]);
+
+    quote!(mod #ident { #(#items)* })
+}
+
+fn report(app: &App, analysis: &Analysis) -> String {
+    let mut out = String::new();
+
+    let device = app
+        .args
+        .device
+        .as_ref()
+        .map(|device| quote!(#device).to_string())
+        .unwrap_or_else(|| "none".to_string());
+    writeln!(
+        out,
+        "app `{}` (device: {}, peripherals: {}, cores: {})",
+        app.name, device, app.args.peripherals, app.args.cores
+    )
+    .unwrap();
+
+    writeln!(out, "\ntasks").unwrap();
+    writeln!(
+        out,
+        "  {:<20} {:<9} {}priority 0",
+        app.init.name.to_string(),
+        "init",
+        on_core(app, app.init.args.core)
+    )
+    .unwrap();
+    if let Some(idle) = &app.idle {
+        writeln!(
+            out,
+            "  {:<20} {:<9} {}priority 0{}",
+            idle.name.to_string(),
+            "idle",
+            on_core(app, idle.args.core),
+            if idle.is_async { ", async" } else { "" }
+        )
+        .unwrap();
+    }
+    for (name, task) in &app.hardware_tasks {
+        writeln!(
+            out,
+            "  {:<20} {:<9} {}priority {}{}, binds {}",
+            name.to_string(),
+            "hardware",
+            on_core(app, task.args.core),
+            task.args.priority,
+            threshold(task.args.threshold),
+            task.args.binds
+        )
+        .unwrap();
+    }
+    for (name, task) in &app.software_tasks {
+        writeln!(
+            out,
+            "  {:<20} {:<9} {}priority {}{}, capacity {}{}",
+            name.to_string(),
+            "software",
+            on_core(app, task.args.core),
+            task.args.priority,
+            threshold(task.args.threshold),
+            task.args.capacity,
+            if task.is_async { ", async" } else { "" }
+        )
+        .unwrap();
+    }
+
+    if !analysis.dispatchers.is_empty() {
+        writeln!(out, "\ndispatchers").unwrap();
+        for (core, dispatchers) in &analysis.dispatchers {
+            for (priority, interrupt) in dispatchers {
+                writeln!(
+                    out,
+                    "  {}priority {}: {}",
+                    on_core(app, *core),
+                    priority,
+                    interrupt
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    if !analysis.vector_table.is_empty() {
+        writeln!(out, "\nvectors").unwrap();
+        for (core, vectors) in &analysis.vector_table {
+            for (name, vector) in vectors {
+                let binding = match vector.binding {
+                    Binding::Interrupt => "interrupt",
+                    Binding::Exception => "exception",
+                    Binding::FixedPriorityException => "exception, fixed priority",
+                };
+                for handler in &vector.handlers {
+                    let cfg = if handler.cfg.is_always() {
+                        String::new()
+                    } else {
+                        format!(" (cfg({}))", handler.cfg)
+                    };
+                    let handler = match &handler.value {
+                        Handler::HardwareTask(task) => format!("task `{}`", task),
+                        Handler::Dispatcher(priority) => {
+                            format!("dispatcher of priority {}", priority)
+                        }
+                        Handler::Monotonic(monotonic) => format!("monotonic `{}`", monotonic),
+                    };
+                    writeln!(
+                        out,
+                        "  {}{}: {} ({}){}",
+                        on_core(app, *core),
+                        name,
+                        handler,
+                        binding,
+                        cfg
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+
+    if !analysis.channels.is_empty() {
+        writeln!(out, "\nchannels").unwrap();
+        for (core, channels) in &analysis.channels {
+            for (priority, channel) in channels {
+                let tasks = channel
+                    .tasks
+                    .iter()
+                    .map(|task| task.to_string())
+                    .collect::<Vec<_>>();
+                writeln!(
+                    out,
+                    "  {}priority {}: capacity {} ({})",
+                    on_core(app, *core),
+                    priority,
+                    channel.capacity,
+                    tasks.join(", ")
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    if !app.shared_resources.is_empty() {
+        writeln!(out, "\nshared resources").unwrap();
+        for (name, res) in &app.shared_resources {
+            let ty = &res.ty;
+            let ownership = match analysis.ownerships.get(name) {
+                _ if !analysis.shared_resources.contains(name) => "dead".to_string(),
+                _ if analysis.atomic_resources.contains(name) => "atomic, never locked".to_string(),
+                Some(ownership) => describe(ownership),
+                None => "unowned".to_string(),
+            };
+
+            let cores = match analysis.locations.get(name) {
+                Some(Location::Shared { cores }) => format!(
+                    ", shared between cores {}",
+                    cores
+                        .iter()
+                        .map(|core| core.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                _ => String::new(),
+            };
+
+            let users = accessors(app, name);
+            writeln!(
+                out,
+                "  {:<20} {:<20} {}{}{}{}",
+                name.to_string(),
+                quote!(#ty).to_string(),
+                ownership,
+                cores,
+                if res.properties.lock_free {
+                    ", lock free"
+                } else {
+                    ""
+                },
+                if users.is_empty() {
+                    String::new()
+                } else {
+                    format!(" [{}]", users.join(", "))
+                }
+            )
+            .unwrap();
+
+            // The ownership in each configuration, if `#[cfg]`s change it
+            let gated = analysis
+                .gated_ownerships
+                .get(name)
+                .map(|gated| &gated[..])
+                .unwrap_or_default();
+            if gated.iter().any(|gated| !gated.cfg.is_always()) {
+                for gated in gated {
+                    writeln!(out, "    cfg({}): {}", gated.cfg, describe(&gated.value)).unwrap();
+                }
+            }
+        }
+    }
+
+    if !app.local_resources.is_empty() {
+        writeln!(out, "\nlocal resources").unwrap();
+        for (name, res) in &app.local_resources {
+            let ty = &res.ty;
+            writeln!(
+                out,
+                "  {:<20} {:<20} {}",
+                name.to_string(),
+                quote!(#ty).to_string(),
+                if analysis.local_resources.contains(name) {
+                    "used"
+                } else {
+                    "dead"
+                }
+            )
+            .unwrap();
+        }
+    }
+
+    if !analysis.response_times.is_empty() {
+        writeln!(out, "\nresponse times").unwrap();
+        for (name, response_time) in &analysis.response_times {
+            let verdict = match response_time.response_time {
+                Some(time) => format!("{:?}", time),
+                None if response_time.missing_timing.is_empty() => {
+                    "exceeds the deadline".to_string()
+                }
+                None => format!(
+                    "unknown, no wcet or period: {}",
+                    response_time
+                        .missing_timing
+                        .iter()
+                        .map(|task| task.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            };
+
+            writeln!(
+                out,
+                "  {:<20} {} (deadline {:?}, wcet {:?}, blocking {:?})",
+                name.to_string(),
+                verdict,
+                response_time.deadline,
+                response_time.wcet,
+                response_time.blocking
+            )
+            .unwrap();
+        }
+    }
+
+    if !analysis.send_types.is_empty() {
+        writeln!(out, "\nSend types").unwrap();
+        for (ty, cfg) in &analysis.send_types {
+            if cfg.is_always() {
+                writeln!(out, "  {}", quote!(#ty)).unwrap();
+            } else {
+                writeln!(out, "  {} (cfg({}))", quote!(#ty), cfg).unwrap();
+            }
+        }
+    }
+
+    if !analysis.sync_types.is_empty() {
+        writeln!(out, "\nSync types").unwrap();
+        for ty in &analysis.sync_types {
+            writeln!(out, "  {}", quote!(#ty)).unwrap();
+        }
+    }
+
+    out
+}
+
+/// How `ownership` is shown in the report
+fn describe(ownership: &Ownership) -> String {
+    match ownership {
+        Ownership::Owned { priority } => format!("owned at priority {}", priority),
+        Ownership::CoOwned { priority } => format!("co-owned at priority {}", priority),
+        Ownership::Contended {
+            ceiling,
+            read_ceiling,
+        } => format!(
+            "contended, ceiling {}, read ceiling {}",
+            ceiling, read_ceiling
+        ),
+        Ownership::ReadOnly { ceiling } => format!("read-only, ceiling {}", ceiling),
+    }
+}
+
+/// `core N, ` in multicore applications, nothing otherwise
+fn on_core(app: &App, core: Core) -> String {
+    if app.args.cores == 1 {
+        String::new()
+    } else {
+        format!("core {}, ", core)
+    }
+}
+
+/// `, threshold N` if the task has a preemption threshold, nothing otherwise
+fn threshold(threshold: Option<u8>) -> String {
+    threshold
+        .map(|threshold| format!(", threshold {}", threshold))
+        .unwrap_or_default()
+}
+
+/// The tasks that access the shared resource `name`, `&task` for shared accesses
+fn accessors(app: &App, name: &syn::Ident) -> Vec<String> {
+    let idle = app
+        .idle
+        .iter()
+        .map(|idle| (&idle.name, &idle.args.shared_resources));
+    let hardware_tasks = app
+        .hardware_tasks
+        .iter()
+        .map(|(task, ht)| (task, &ht.args.shared_resources));
+    let software_tasks = app
+        .software_tasks
+        .iter()
+        .map(|(task, st)| (task, &st.args.shared_resources));
+
+    idle.chain(hardware_tasks)
+        .chain(software_tasks)
+        .filter_map(|(task, resources)| {
+            resources.get(name).map(|access| {
+                let user = match access.access {
+                    Access::Exclusive => task.to_string(),
+                    Access::Shared => format!("&{}", task),
+                };
+                let cfg = Cfg::from_attrs(&access.cfgs);
+                if cfg.is_always() {
+                    user
+                } else {
+                    format!("{} (cfg({}))", user, cfg)
+                }
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inspect(source: &str) -> String {
+        let file = syn::parse_file(source).unwrap();
+        let (args, input) = find_app(&file.items).unwrap();
+        let (app, analysis) = rtic_syntax::parse2(args, input, Settings::default()).unwrap();
+
+        report(&app, &analysis)
+    }
+
+    #[test]
+    fn find_rtic_app() {
+        let report = inspect(
+            "
+            #![no_main]
+
+            #[rtic::app(device = lm3s6965, dispatchers = [UART0])]
+            pub mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                    b: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(capacity = 2, shared = [&a])]
+                fn foo(_: foo::Context) {}
+            }
+            ",
+        );
+
+        assert!(report.contains("app `app` (device: lm3s6965, peripherals: true, cores: 1)"));
+        assert!(report.contains("priority 1: UART0"));
+        assert!(report.contains("UART0: dispatcher of priority 1 (interrupt)"));
+        assert!(report.contains("priority 1: capacity 2 (foo)"));
+        assert!(report.contains("owned at priority 1 [&foo]"));
+        assert!(report
+            .lines()
+            .any(|line| line.starts_with("  b ") && line.ends_with("dead")));
+    }
+
+    #[test]
+    fn multicore() {
+        let report = inspect(
+            "
+            #[rtic::app(device = lpc54114, cores = 2, dispatchers = [A, B])]
+            mod app {
+                #[shared]
+                struct Shared {
+                    x: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [x])]
+                fn foo(_: foo::Context) {}
+
+                #[task(core = 1, shared = [x])]
+                fn bar(_: bar::Context) {}
+            }
+            ",
+        );
+
+        assert!(report.contains("core 1, priority 1: B"));
+        assert!(report.contains("core 1, priority 1: capacity 1 (bar)"));
+        assert!(report.contains("shared between cores 0, 1"));
+    }
+
+    #[test]
+    fn response_times() {
+        let report = inspect(
+            "
+            #[rtic::app(device = lm3s6965, dispatchers = [A, B])]
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(priority = 2, wcet = 100us, period = 1ms)]
+                fn foo(_: foo::Context) {}
+
+                #[task(wcet = 300us, period = 2ms, deadline = 500us)]
+                fn bar(_: bar::Context) {}
+            }
+            ",
+        );
+
+        assert!(report.contains("400µs (deadline 500µs, wcet 300µs, blocking 0ns)"));
+    }
+
+    #[test]
+    fn cfgs() {
+        let report = inspect(
+            r#"
+            #[rtic::app(device = lm3s6965, dispatchers = [A, B])]
+            mod app {
+                #[shared]
+                struct Shared {
+                    x: u32,
+                    y: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [x])]
+                fn foo(_: foo::Context) {}
+
+                #[task(shared = [#[cfg(debug_assertions)] &y])]
+                fn bar(_: bar::Context) {}
+
+                #[cfg(feature = "radio")]
+                #[task(priority = 2, shared = [x])]
+                fn radio(_: radio::Context, _: Packet) {}
+            }
+            "#,
+        );
+
+        assert!(report.contains(r#"cfg(not(feature = "radio")): owned at priority 1"#));
+        assert!(report.contains(r#"cfg(feature = "radio"): contended, ceiling 2"#));
+        assert!(report.contains(r#"Packet (cfg(feature = "radio"))"#));
+        assert!(report.contains("&bar (cfg(debug_assertions))"));
+    }
+
+    #[test]
+    fn cfg_options() {
+        let options = parse_options(
+            [
+                "--cfg",
+                "debug_assertions",
+                "--cfg",
+                r#"target_arch="arm""#,
+                "--features",
+                "radio,usb",
+                "app.rs",
+            ]
+            .iter()
+            .map(|arg| arg.to_string()),
+        )
+        .ok()
+        .unwrap();
+        let set = options.settings.cfg.unwrap();
+        assert!(set.contains("debug_assertions", None));
+        assert!(set.contains("target_arch", Some("arm")));
+        assert!(set.contains("feature", Some("radio")));
+        assert!(set.contains("feature", Some("usb")));
+
+        let error = parse_options(["--cfg", "all(x)"].iter().map(|arg| arg.to_string()))
+            .err()
+            .unwrap();
+        assert!(error.starts_with("error: `all(x)` is not a cfg option"));
+    }
+
+    #[test]
+    fn device_option() {
+        let error = parse_options(["app.rs", "--device"].iter().map(|arg| arg.to_string()))
+            .err()
+            .unwrap();
+        assert!(error.starts_with("error: `--device` needs a DEVICE"));
+
+        let error = parse_options(
+            ["--device", "missing.txt"]
+                .iter()
+                .map(|arg| arg.to_string()),
+        )
+        .err()
+        .unwrap();
+        assert!(error.starts_with("error: couldn't read `missing.txt`"));
+    }
+
+    #[test]
+    fn no_app() {
+        let file = syn::parse_file("mod app {}").unwrap();
+
+        assert!(find_app(&file.items).is_none());
+    }
+}
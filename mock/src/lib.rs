@@ -5,25 +5,78 @@
 extern crate proc_macro;
 
 use proc_macro::TokenStream;
-use rtic_syntax::Settings;
+use proc_macro2::{TokenStream as TokenStream2, TokenTree};
+use quote::quote;
+use rtic_syntax::{lint::Level, Settings};
 
 #[proc_macro_attribute]
 pub fn app(args: TokenStream, input: TokenStream) -> TokenStream {
     let mut settings = Settings::default();
     let mut rtic_args = vec![];
-    for arg in args.to_string().split(',') {
-        if arg.trim() == "parse_binds" {
+    for tokens in split_args(args.into()) {
+        let arg = tokens.to_string();
+        let lint = arg.trim().replace(' ', "");
+        let lint = lint
+            .strip_prefix("allow(")
+            .map(|lint| (lint, Level::Allow))
+            .or_else(|| lint.strip_prefix("deny(").map(|lint| (lint, Level::Deny)));
+
+        if let Some((lint, level)) = lint {
+            match lint.trim_end_matches(')') {
+                "unused_shared_resource" => settings.lints.unused_shared_resource = level,
+                "single_task_shared_resource" => settings.lints.single_task_shared_resource = level,
+                "unused_dispatcher" => settings.lints.unused_dispatcher = level,
+                "unschedulable_task" => settings.lints.unschedulable_task = level,
+                "incomplete_timing" => settings.lints.incomplete_timing = level,
+                "unused_resource_access" => settings.lints.unused_resource_access = level,
+                "undeclared_resource_access" => settings.lints.undeclared_resource_access = level,
+                "never_spawned" => settings.lints.never_spawned = level,
+                "inconsistent_cfg" => settings.lints.inconsistent_cfg = level,
+                lint => panic!("unknown lint `{}`", lint),
+            }
+        } else if arg.trim() == "parse_binds" {
             settings.parse_binds = true;
         } else if arg.trim() == "parse_extern_interrupt" {
             settings.parse_extern_interrupt = true;
+        } else if arg.trim() == "parse_async_tasks" {
+            settings.parse_async_tasks = true;
+        } else if arg.trim() == "precise_send_types" {
+            settings.precise_send_types = true;
         } else {
-            rtic_args.push(arg.to_string());
+            rtic_args.push(tokens);
+        }
+    }
+
+    match rtic_syntax::parse(quote!(#(#rtic_args),*).into(), input, settings) {
+        Err(e) => e.to_compile_error().into(),
+        Ok((_app, analysis)) => {
+            let warnings = &analysis.warnings;
+
+            quote!(
+                fn main() {}
+
+                #(#warnings)*
+            )
+            .into()
         }
     }
+}
 
-    if let Err(e) = rtic_syntax::parse(rtic_args.join(", ").parse().unwrap(), input, settings) {
-        e.to_compile_error().into()
-    } else {
-        "fn main() {}".parse().unwrap()
+/// Splits the arguments at the top level commas, keeping the spans of the tokens
+fn split_args(args: TokenStream2) -> Vec<TokenStream2> {
+    let mut split = vec![];
+    let mut current = vec![];
+    for tt in args {
+        match tt {
+            TokenTree::Punct(punct) if punct.as_char() == ',' => {
+                split.push(current.drain(..).collect());
+            }
+            tt => current.push(tt),
+        }
     }
+    if !current.is_empty() {
+        split.push(current.into_iter().collect());
+    }
+
+    split
 }
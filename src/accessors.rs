@@ -1,38 +1,118 @@
-use syn::Ident;
+use syn::{Attribute, Ident};
 
 use crate::{
-    analyze::Priority,
+    analyze::{Core, Priority},
     ast::{Access, App, Local, TaskLocal},
+    cfg::Cfg,
 };
 
+/// A context that accesses a shared resource
+#[derive(Clone, Copy)]
+pub(crate) struct User<'a> {
+    pub(crate) core: Core,
+    pub(crate) priority: Priority,
+    /// Preemption threshold
+    pub(crate) threshold: Priority,
+    pub(crate) access: Access,
+    /// `#[cfg]` attributes of the context; `#[idle]` has none
+    pub(crate) cfgs: &'a [Attribute],
+    /// `#[cfg]` attributes of the `shared = [..]` entry
+    pub(crate) entry_cfgs: &'a [Attribute],
+}
+
+impl User<'_> {
+    /// The condition under which this context accesses the resource
+    pub(crate) fn cfg(&self) -> Cfg {
+        Cfg::from_attrs(self.cfgs).and(Cfg::from_attrs(self.entry_cfgs))
+    }
+}
+
 impl App {
-    pub(crate) fn shared_resource_accesses(
-        &self,
-    ) -> impl Iterator<Item = (Option<Priority>, &Ident, Access)> {
+    /// Every `shared = [..]` entry of every context, paired with the context that lists it
+    pub(crate) fn shared_resource_accesses(&self) -> impl Iterator<Item = (&Ident, User<'_>)> {
         self.idle
             .iter()
             .flat_map(|idle| {
                 idle.args
                     .shared_resources
                     .iter()
-                    .map(move |(name, access)| (Some(0), name, *access))
+                    .map(move |(name, access)| {
+                        (
+                            name,
+                            User {
+                                core: idle.args.core,
+                                priority: 0,
+                                threshold: 0,
+                                access: access.access,
+                                cfgs: &[],
+                                entry_cfgs: &access.cfgs,
+                            },
+                        )
+                    })
             })
             .chain(self.hardware_tasks.values().flat_map(|task| {
                 task.args
                     .shared_resources
                     .iter()
-                    .map(move |(name, access)| (Some(task.args.priority), name, *access))
+                    .map(move |(name, access)| {
+                        (
+                            name,
+                            User {
+                                core: task.args.core,
+                                priority: task.args.priority,
+                                threshold: task.args.threshold(),
+                                access: access.access,
+                                cfgs: &task.cfgs,
+                                entry_cfgs: &access.cfgs,
+                            },
+                        )
+                    })
             }))
             .chain(self.software_tasks.values().flat_map(|task| {
                 task.args
                     .shared_resources
                     .iter()
-                    .map(move |(name, access)| (Some(task.args.priority), name, *access))
+                    .map(move |(name, access)| {
+                        (
+                            name,
+                            User {
+                                core: task.args.core,
+                                priority: task.args.priority,
+                                threshold: task.args.threshold(),
+                                access: access.access,
+                                cfgs: &task.cfgs,
+                                entry_cfgs: &access.cfgs,
+                            },
+                        )
+                    })
             }))
     }
 
+    /// Whether all the accesses to the shared resource `name` are shared (`&x`) accesses,
+    /// ignoring the ones whose `#[cfg]`s exclude the resource itself
+    pub(crate) fn is_read_only(&self, name: &Ident) -> bool {
+        let resource = self
+            .shared_resources
+            .get(name)
+            .map(|resource| Cfg::from_attrs(&resource.cfgs))
+            .unwrap_or_else(Cfg::always);
+
+        self.shared_resource_users(name)
+            .all(|user| user.access.is_shared() || !user.cfg().overlaps(&resource))
+    }
+
+    /// The contexts that access the shared resource `name`
+    pub(crate) fn shared_resource_users<'a>(
+        &'a self,
+        name: &'a Ident,
+    ) -> impl Iterator<Item = User<'a>> + 'a {
+        self.shared_resource_accesses()
+            .filter(move |(resource, _)| *resource == name)
+            .map(|(_, user)| user)
+    }
+
     fn is_external(task_local: &TaskLocal) -> bool {
-        matches!(task_local, TaskLocal::External)
+        matches!(task_local, TaskLocal::External { .. })
     }
 
     pub(crate) fn local_resource_accesses(&self) -> impl Iterator<Item = &Ident> {
@@ -67,7 +147,7 @@ impl App {
 
     fn get_declared_local(tl: &TaskLocal) -> Option<&Local> {
         match tl {
-            TaskLocal::External => None,
+            TaskLocal::External { .. } => None,
             TaskLocal::Declared(l) => Some(l),
         }
     }
@@ -1,96 +1,74 @@
 //! RTIC application analysis
 
-use core::cmp;
-use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet};
 
 use indexmap::{IndexMap, IndexSet};
 use syn::{Ident, Type};
 
 use crate::{
-    ast::{App, LocalResources, TaskLocal},
-    Set,
+    accessors::User,
+    ast::{Access, App, Binding, LocalResources, TaskLocal},
+    body,
+    cfg::{self, Cfg, Gated},
+    lint::Warning,
+    schedule::ResponseTimes,
+    Map, Set, Settings,
 };
 
-pub(crate) fn app(app: &App) -> Result<Analysis, syn::Error> {
+pub(crate) fn app(app: &App, settings: &Settings) -> Result<Analysis, syn::Error> {
     // Collect all tasks into a vector
     type TaskName = String;
-    type Priority = u8;
 
-    // The task list is a Tuple (Name, Shared Resources, Local Resources, Priority)
-    let task_resources_list: Vec<(TaskName, Vec<&Ident>, &LocalResources, Priority)> =
-        Some(&app.init)
-            .iter()
-            .map(|ht| ("init".to_string(), Vec::new(), &ht.args.local_resources, 0))
-            .chain(app.idle.iter().map(|ht| {
-                (
-                    "idle".to_string(),
-                    ht.args
-                        .shared_resources
-                        .iter()
-                        .map(|(v, _)| v)
-                        .collect::<Vec<_>>(),
-                    &ht.args.local_resources,
-                    0,
-                )
-            }))
-            .chain(app.software_tasks.iter().map(|(name, ht)| {
-                (
-                    name.to_string(),
-                    ht.args
-                        .shared_resources
-                        .iter()
-                        .map(|(v, _)| v)
-                        .collect::<Vec<_>>(),
-                    &ht.args.local_resources,
-                    ht.args.priority,
-                )
-            }))
-            .chain(app.hardware_tasks.iter().map(|(name, ht)| {
-                (
-                    name.to_string(),
-                    ht.args
-                        .shared_resources
-                        .iter()
-                        .map(|(v, _)| v)
-                        .collect::<Vec<_>>(),
-                    &ht.args.local_resources,
-                    ht.args.priority,
-                )
-            }))
-            .collect();
+    // The task list is a Tuple (Name, Local Resources, `#[cfg]`s)
+    let task_resources_list: Vec<(TaskName, &LocalResources, &[syn::Attribute])> = Some(&app.init)
+        .iter()
+        .map(|ht| ("init".to_string(), &ht.args.local_resources, &[][..]))
+        .chain(
+            app.idle
+                .iter()
+                .map(|ht| ("idle".to_string(), &ht.args.local_resources, &[][..])),
+        )
+        .chain(
+            app.software_tasks
+                .iter()
+                .map(|(name, ht)| (name.to_string(), &ht.args.local_resources, &ht.cfgs[..])),
+        )
+        .chain(
+            app.hardware_tasks
+                .iter()
+                .map(|(name, ht)| (name.to_string(), &ht.args.local_resources, &ht.cfgs[..])),
+        )
+        .collect();
 
     let mut error = vec![];
     let mut lf_res_with_error = vec![];
-    let mut lf_hash = HashMap::new();
 
-    // Collect lock free resources
+    // Collect lock free resources; read-only ones can be used from any priority as they must be
+    // `Sync`
     let lock_free: Vec<&Ident> = app
         .shared_resources
         .iter()
-        .filter(|(_, r)| r.properties.lock_free)
+        .filter(|(i, r)| r.properties.lock_free && !app.is_read_only(i))
         .map(|(i, _)| i)
         .collect();
 
-    // Check that lock_free resources are correct
+    // Check that lock_free resources are correct: an exclusive access can't be compiled together
+    // with an access from another priority
     for lf_res in lock_free.iter() {
-        for (task, tr, _, priority) in task_resources_list.iter() {
-            for r in tr {
-                // Get all uses of resources annotated lock_free
-                if lf_res == r {
-                    // HashMap returns the previous existing object if old.key == new.key
-                    if let Some(lf_res) = lf_hash.insert(r.to_string(), (task, r, priority)) {
-                        // Check if priority differ, if it does, append to
-                        // list of resources which will be annotated with errors
-                        if priority != lf_res.2 {
-                            lf_res_with_error.push(lf_res.1);
-                            lf_res_with_error.push(r);
-                        }
-                        // If the resource already violates lock free properties
-                        if lf_res_with_error.contains(&r) {
-                            lf_res_with_error.push(lf_res.1);
-                            lf_res_with_error.push(r);
-                        }
-                    }
+        // Get all uses of resources annotated lock_free
+        let uses = app
+            .shared_resource_accesses()
+            .filter(|(r, _)| r == lf_res)
+            .collect::<Vec<_>>();
+
+        for (i, (r1, user1)) in uses.iter().enumerate() {
+            for (r2, user2) in &uses[i + 1..] {
+                if user1.priority != user2.priority
+                    && (user1.access.is_exclusive() || user2.access.is_exclusive())
+                    && user1.cfg().overlaps(&user2.cfg())
+                {
+                    lf_res_with_error.push(*r1);
+                    lf_res_with_error.push(*r2);
                 }
             }
         }
@@ -98,7 +76,7 @@ pub(crate) fn app(app: &App) -> Result<Analysis, syn::Error> {
 
     // Add error message in the resource struct
     for r in lock_free {
-        if lf_res_with_error.contains(&&r) {
+        if lf_res_with_error.contains(&r) {
             error.push(syn::Error::new(
                 r.span(),
                 format!(
@@ -124,21 +102,23 @@ pub(crate) fn app(app: &App) -> Result<Analysis, syn::Error> {
     let local: Vec<&Ident> = app.local_resources.iter().map(|(i, _)| i).collect();
 
     let mut lr_with_error = vec![];
-    let mut lr_hash = HashMap::new();
 
-    // Check that local resources are not shared
+    // Check that local resources are not shared by tasks that can be compiled together
     for lr in local {
-        for (task, _, local_resources, _) in task_resources_list.iter() {
+        let mut uses: Vec<(&TaskName, &Ident, Cfg)> = vec![];
+        for (task, local_resources, cfgs) in task_resources_list.iter() {
             for (name, res) in local_resources.iter() {
-                // Get all uses of resources annotated lock_free
                 if lr == name {
                     match res {
-                        TaskLocal::External => {
-                            // HashMap returns the previous existing object if old.key == new.key
-                            if let Some(lr) = lr_hash.insert(name.to_string(), (task, name)) {
-                                lr_with_error.push(lr.1);
-                                lr_with_error.push(name);
+                        TaskLocal::External { cfgs: entry_cfgs } => {
+                            let cfg = Cfg::from_attrs(cfgs).and(Cfg::from_attrs(entry_cfgs));
+                            for (other_task, other, other_cfg) in &uses {
+                                if *other_task != task && other_cfg.overlaps(&cfg) {
+                                    lr_with_error.push(*other);
+                                    lr_with_error.push(name);
+                                }
                             }
+                            uses.push((task, name, cfg));
                         }
                         // If a declared local has the same name as the `#[local]` struct, it's an
                         // direct error
@@ -164,57 +144,118 @@ pub(crate) fn app(app: &App) -> Result<Analysis, syn::Error> {
     }
 
     // Collect errors if any and return/halt
-    if !error.is_empty() {
-        let mut err = error.get(0).unwrap().clone();
-        error.iter().for_each(|e| err.combine(e.clone()));
-        return Err(err);
-    }
+    crate::combine_errors(error)?;
 
     // e. Location of resources
     let mut used_shared_resource = IndexSet::new();
     let mut ownerships = Ownerships::new();
     let mut sync_types = SyncTypes::new();
-    for (prio, name, access) in app.shared_resource_accesses() {
-        let res = app.shared_resources.get(name).expect("UNREACHABLE");
+    let mut locations = Locations::new();
+    let mut atomic_resources = IndexSet::new();
+    for (name, user) in app.shared_resource_accesses() {
+        // An access whose `#[cfg]`s exclude the resource never happens
+        let exists = app
+            .shared_resources
+            .get(name)
+            .map_or(true, |res| Cfg::from_attrs(&res.cfgs).overlaps(&user.cfg()));
+        if !exists {
+            continue;
+        }
+
+        // Cores that access this resource
+        let core = user.core;
+        match locations.get_mut(name) {
+            Some(Location::Owned { core: owner }) if *owner != core => {
+                let cores = [*owner, core].iter().copied().collect();
+                locations.insert(name.clone(), Location::Shared { cores });
+            }
+            Some(Location::Shared { cores }) => {
+                cores.insert(core);
+            }
+            Some(Location::Owned { .. }) => {}
+            None => {
+                locations.insert(name.clone(), Location::Owned { core });
+            }
+        }
 
         // (e)
         // This shared resource is used
         used_shared_resource.insert(name.clone());
 
-        // (c)
-        if let Some(priority) = prio {
-            if let Some(ownership) = ownerships.get_mut(name) {
-                match *ownership {
-                    Ownership::Owned { priority: ceiling }
-                    | Ownership::CoOwned { priority: ceiling }
-                    | Ownership::Contended { ceiling }
-                        if priority != ceiling =>
-                    {
-                        *ownership = Ownership::Contended {
-                            ceiling: cmp::max(ceiling, priority),
-                        };
-
-                        if access.is_shared() {
-                            sync_types.insert(res.ty.clone());
-                        }
-                    }
+        // Atomic resources are never locked so they have no ceiling
+        if matches!(app.shared_resources.get(name), Some(res) if res.properties.atomic) {
+            atomic_resources.insert(name.clone());
+        }
+    }
 
-                    Ownership::Owned { priority: ceil } if ceil == priority => {
-                        *ownership = Ownership::CoOwned { priority };
-                    }
+    // (c)
+    let mut gated_ownerships = GatedOwnerships::new();
+    for name in &used_shared_resource {
+        if atomic_resources.contains(name) {
+            continue;
+        }
 
-                    _ => {}
-                }
-            } else {
-                ownerships.insert(name.clone(), Ownership::Owned { priority });
+        let users = app.shared_resource_users(name).collect::<Vec<_>>();
+        if let Some(ownership) = ownership(&users) {
+            ownerships.insert(name.clone(), ownership);
+        }
+
+        // The ownership in each configuration; users without `#[cfg]` are always there
+        let resource_cfg = Cfg::from_attrs(&app.shared_resources[name].cfgs);
+        let user_cfgs = users.iter().map(|user| user.cfg()).collect::<Vec<_>>();
+        let mut cfgs = vec![];
+        for cfg in &user_cfgs {
+            if !cfg.is_always() && !cfgs.contains(cfg) {
+                cfgs.push(cfg.clone());
             }
         }
+
+        let split = cfg::split(&cfgs, |holds| {
+            let users = users
+                .iter()
+                .zip(&user_cfgs)
+                .filter(|(_, cfg)| {
+                    cfg.is_always() || holds[cfgs.iter().position(|c| c == *cfg).unwrap()]
+                })
+                .map(|(user, _)| *user)
+                .collect::<Vec<_>>();
+
+            ownership(&users)
+        });
+        gated_ownerships.insert(
+            name.clone(),
+            split
+                .into_iter()
+                .map(|gated| Gated {
+                    cfg: resource_cfg.clone().and(gated.cfg),
+                    value: gated.value,
+                })
+                .collect(),
+        );
+    }
+
+    // Contended resources, and resources shared between cores, that are accessed through a
+    // shared reference need to be `Sync`
+    for (name, user) in app.shared_resource_accesses() {
+        let contended = matches!(
+            ownerships.get(name),
+            Some(Ownership::Contended { .. } | Ownership::ReadOnly { .. })
+        ) || matches!(locations.get(name), Some(Location::Shared { .. }));
+
+        if user.access.is_shared() && contended {
+            sync_types.insert(app.shared_resources[name].ty.clone());
+        }
+    }
+
+    // Atomic resources are accessed concurrently, without locks, from any priority or core
+    for name in &atomic_resources {
+        sync_types.insert(app.shared_resources[name].ty.clone());
     }
 
     // Create the list of used local resource Idents
     let mut used_local_resource = IndexSet::new();
 
-    for (_, _, locals, _) in task_resources_list {
+    for (_, locals, _) in task_resources_list {
         for (local, _) in locals {
             used_local_resource.insert(local.clone());
         }
@@ -222,15 +263,22 @@ pub(crate) fn app(app: &App) -> Result<Analysis, syn::Error> {
 
     // Most shared resources need to be `Send`
     let mut send_types = SendTypes::new();
+    let mut require_send = |ty: &Type, cfg: Cfg| match send_types.get_mut(ty) {
+        Some(condition) => *condition = condition.clone().or(cfg),
+        None => {
+            send_types.insert(Box::new(ty.clone()), cfg);
+        }
+    };
     let owned_by_idle = Ownership::Owned { priority: 0 };
     for (name, res) in app.shared_resources.iter() {
-        // Handle not owned by idle
+        // Handle not owned by idle; atomic resources are created in `init` too
         if ownerships
             .get(name)
             .map(|ownership| *ownership != owned_by_idle)
             .unwrap_or(false)
+            || atomic_resources.contains(name)
         {
-            send_types.insert(res.ty.clone());
+            require_send(&res.ty, Cfg::from_attrs(&res.cfgs));
         }
     }
 
@@ -239,55 +287,282 @@ pub(crate) fn app(app: &App) -> Result<Analysis, syn::Error> {
         if let Some(idle) = &app.idle {
             // Only Send if not in idle
             if idle.args.local_resources.get(name).is_none() {
-                send_types.insert(res.ty.clone());
+                require_send(&res.ty, Cfg::from_attrs(&res.cfgs));
             }
         } else {
-            send_types.insert(res.ty.clone());
+            require_send(&res.ty, Cfg::from_attrs(&res.cfgs));
         }
     }
 
+    let (bodies, user_code_spawns) = body::app(app);
+
+    // Tasks spawned from code we can't see (`extern` tasks or code outside the `#[app]` module)
+    let opaque_spawners = app.hardware_tasks.values().any(|task| task.is_extern)
+        || app.software_tasks.values().any(|task| task.is_extern);
+
     let mut channels = Channels::new();
+    let mut async_priorities = AsyncPriorities::new();
 
     for (name, spawnee) in &app.software_tasks {
+        let spawnee_core = spawnee.args.core;
         let spawnee_prio = spawnee.args.priority;
 
-        let channel = channels.entry(spawnee_prio).or_default();
+        let channel = channels
+            .entry(spawnee_core)
+            .or_default()
+            .entry(spawnee_prio)
+            .or_default();
         channel.tasks.insert(name.clone());
-
-        // All inputs are now send as we do not know from where they may be spawned.
-        spawnee.inputs.iter().for_each(|input| {
-            send_types.insert(input.ty.clone());
+        channel.capacities.push(Gated {
+            cfg: Cfg::from_attrs(&spawnee.cfgs),
+            value: spawnee.args.capacity,
         });
+
+        // Async tasks are polled by an executor running at the task priority
+        if spawnee.is_async {
+            async_priorities
+                .entry(spawnee_core)
+                .or_default()
+                .insert(spawnee_prio);
+        }
+
+        // The inputs must be `Send` unless the message provably stays at the priority (and core)
+        // it was sent from: every spawner is visible, uses `spawn` and runs at the priority and
+        // on the core of the spawnee. Tasks can also be spawned from outside the `#[app]` module
+        // so this is opt-in
+        let spawners = bodies
+            .iter()
+            .filter(|(_, body)| body.spawns.contains(name))
+            .collect::<Vec<_>>();
+        let same_context = settings.precise_send_types
+            && !opaque_spawners
+            && !user_code_spawns.contains(name)
+            && !spawners.is_empty()
+            && spawners.iter().all(|(spawner, body)| {
+                !body.timed_spawns.contains(name)
+                    && context_placements(app, spawner)
+                        .iter()
+                        .all(|placement| *placement == (spawnee_core, spawnee_prio))
+            });
+
+        if !same_context {
+            spawnee.inputs.iter().for_each(|input| {
+                require_send(&input.ty, Cfg::from_attrs(&spawnee.cfgs));
+            });
+        }
     }
 
     // No channel should ever be empty
-    debug_assert!(channels.values().all(|channel| !channel.tasks.is_empty()));
+    debug_assert!(channels
+        .values()
+        .flat_map(|channels| channels.values())
+        .all(|channel| !channel.tasks.is_empty()));
+
+    // Compute channel capacities: the largest sum of the terms that can be compiled together, so
+    // the variants of a task under mutually exclusive `#[cfg]`s don't add up
+    let mut errors = vec![];
+    for (priority, channel) in channels
+        .values_mut()
+        .flat_map(|channels| channels.iter_mut())
+    {
+        let mut cfgs = vec![];
+        for capacity in &channel.capacities {
+            if !capacity.cfg.is_always() && !cfgs.contains(&capacity.cfg) {
+                cfgs.push(capacity.cfg.clone());
+            }
+        }
+
+        let sums = cfg::split(&cfgs, |holds| {
+            Some(
+                channel
+                    .capacities
+                    .iter()
+                    .filter(|capacity| {
+                        capacity.cfg.is_always()
+                            || holds[cfgs.iter().position(|c| *c == capacity.cfg).unwrap()]
+                    })
+                    .try_fold(0u8, |sum, capacity| sum.checked_add(capacity.value)),
+            )
+        });
 
-    // Compute channel capacities
-    for channel in channels.values_mut() {
-        channel.capacity = channel
-            .tasks
+        match sums
             .iter()
-            .map(|name| app.software_tasks[name].args.capacity)
-            .sum();
+            .try_fold(0, |max, sum| sum.value.map(|sum| max.max(sum)))
+        {
+            Some(capacity) => channel.capacity = capacity,
+            None => {
+                let task = channel.tasks.iter().next().expect("UNREACHABLE");
+                errors.push(syn::Error::new(
+                    task.span(),
+                    format!(
+                        "the capacities of the tasks of priority {} add up to more than {}",
+                        priority,
+                        u8::MAX
+                    ),
+                ));
+            }
+        }
+    }
+    crate::combine_errors(errors)?;
+
+    // Assign dispatchers, in declaration order, core by core, starting from the highest priority
+    let mut dispatchers = Dispatchers::new();
+    let levels = channels.iter().flat_map(|(core, channels)| {
+        channels
+            .keys()
+            .rev()
+            .map(move |priority| (*core, *priority))
+    });
+    for ((core, priority), interrupt) in levels.zip(app.args.extern_interrupts.keys()) {
+        dispatchers
+            .entry(core)
+            .or_default()
+            .insert(priority, interrupt.clone());
+    }
+
+    let mut vector_table = VectorTable::new();
+    let mut bind = |core: Core, vector: &Ident, binding: Binding, handler: Gated<Handler>| {
+        vector_table
+            .entry(core)
+            .or_default()
+            .entry(vector.clone())
+            .or_insert_with(|| Vector {
+                binding,
+                handlers: vec![],
+            })
+            .handlers
+            .push(handler);
+    };
+    for (name, task) in &app.hardware_tasks {
+        bind(
+            task.args.core,
+            &task.args.binds,
+            task.binding,
+            Gated {
+                cfg: Cfg::from_attrs(&task.cfgs),
+                value: Handler::HardwareTask(name.clone()),
+            },
+        );
+    }
+    for (name, monotonic) in &app.monotonics {
+        bind(
+            monotonic.args.core,
+            &monotonic.args.binds,
+            monotonic.binding,
+            Gated {
+                cfg: Cfg::from_attrs(&monotonic.cfgs),
+                value: Handler::Monotonic(name.clone()),
+            },
+        );
+    }
+    for (core, dispatchers) in &dispatchers {
+        for (priority, interrupt) in dispatchers {
+            bind(
+                *core,
+                interrupt,
+                Binding::Interrupt,
+                Gated {
+                    cfg: Cfg::always(),
+                    value: Handler::Dispatcher(*priority),
+                },
+            );
+        }
     }
 
     Ok(Analysis {
+        async_priorities,
         channels,
+        dispatchers,
+        vector_table,
         shared_resources: used_shared_resource,
+        atomic_resources,
         local_resources: used_local_resource,
+        locations,
         ownerships,
+        gated_ownerships,
         send_types,
         sync_types,
+        bodies,
+        user_code_spawns,
+        response_times: ResponseTimes::new(),
+        warnings: vec![],
     })
 }
 
+/// The ownership of a shared resource accessed by `users`, `None` if there are none
+fn ownership(users: &[User<'_>]) -> Option<Ownership> {
+    let ceiling = users.iter().map(|user| user.priority).max()?;
+
+    // Tasks on different cores run in parallel, whatever their priorities
+    let single_core = users.iter().all(|user| user.core == users[0].core);
+
+    if single_core && users.iter().all(|user| user.priority == ceiling) {
+        return Some(if users.len() == 1 {
+            Ownership::Owned { priority: ceiling }
+        } else {
+            Ownership::CoOwned { priority: ceiling }
+        });
+    }
+
+    // Readers only need to lock out the writers, and without writers there's nothing to lock
+    let read_ceiling = match users
+        .iter()
+        .filter(|user| user.access.is_exclusive())
+        .map(|user| user.priority)
+        .max()
+    {
+        Some(read_ceiling) => read_ceiling,
+        None => return Some(Ownership::ReadOnly { ceiling }),
+    };
+
+    // Tasks whose preemption thresholds keep them from preempting each other don't contend for
+    // a resource, they access it in turns like tasks of the same priority
+    let preemptible = users.iter().enumerate().any(|(i, user)| {
+        users
+            .iter()
+            .enumerate()
+            .any(|(j, other)| i != j && other.priority > user.threshold)
+    });
+
+    Some(if single_core && !preemptible {
+        Ownership::CoOwned { priority: ceiling }
+    } else {
+        Ownership::Contended {
+            ceiling,
+            read_ceiling,
+        }
+    })
+}
+
+/// The core and priority `context` runs at, one per variant of a task; empty if there's no such
+/// context
+fn context_placements(app: &App, context: &Ident) -> Vec<(Core, Priority)> {
+    if *context == app.init.name {
+        vec![(app.init.args.core, 0)]
+    } else if let Some(idle) = app.idle.as_ref().filter(|idle| idle.name == *context) {
+        vec![(idle.args.core, 0)]
+    } else {
+        app.hardware_tasks
+            .variants(context)
+            .map(|task| (task.args.core, task.args.priority))
+            .chain(
+                app.software_tasks
+                    .variants(context)
+                    .map(|task| (task.args.core, task.args.priority)),
+            )
+            .collect()
+    }
+}
+
 /// Priority ceiling
 pub type Ceiling = Option<u8>;
 
 /// Task priority
 pub type Priority = u8;
 
+/// Core index, from 0 to `cores - 1`
+pub type Core = u8;
+
 /// Resource name
 pub type Resource = Ident;
 
@@ -296,33 +571,96 @@ pub type Task = Ident;
 
 /// The result of analyzing an RTIC application
 pub struct Analysis {
-    /// SPSC message channels
+    /// Priority levels, of each core, that host at least one `async` software task
+    ///
+    /// The backend must generate an async executor for each of these priorities
+    pub async_priorities: AsyncPriorities,
+
+    /// SPSC message channels, of each core
     pub channels: Channels,
 
+    /// The interrupt that dispatches the software tasks of each priority level, of each core
+    pub dispatchers: Dispatchers,
+
+    /// Every vector the application binds a handler to, of each core: the interrupts and
+    /// exceptions of the hardware tasks and monotonics, and the dispatchers in `dispatchers`
+    pub vector_table: VectorTable,
+
     /// Shared resources
     ///
     /// If a resource is not listed here it means that's a "dead" (never
     /// accessed) resource and the backend should not generate code for it
     pub shared_resources: UsedSharedResource,
 
+    /// The used `#[atomic]` shared resources
+    ///
+    /// These are never locked so they have no entry in `ownerships`; their types are in
+    /// `sync_types`
+    pub atomic_resources: UsedSharedResource,
+
     /// Local resources
     ///
     /// If a resource is not listed here it means that's a "dead" (never
     /// accessed) resource and the backend should not generate code for it
     pub local_resources: UsedLocalResource,
 
+    /// The core(s) that access each shared resource
+    pub locations: Locations,
+
     /// Resource ownership
+    ///
+    /// Priorities are only comparable within a core; the ceilings of a resource shared between
+    /// cores are computed over all the cores, which is conservative
     pub ownerships: Ownerships,
 
+    /// Resource ownership under `#[cfg]`: the ownership of each resource in the configurations
+    /// where it's used, when only the users whose `#[cfg]` holds exist
+    ///
+    /// The conditions of a resource are mutually exclusive and include the resource's own
+    /// `#[cfg]`; `ownerships` is the ownership when every user exists, with all features enabled
+    pub gated_ownerships: GatedOwnerships,
+
     /// These types must implement the `Send` trait
+    ///
+    /// Each type is tagged with the `#[cfg]` condition under which it must be `Send`: that of
+    /// the resources and software tasks that require it
     pub send_types: SendTypes,
 
     /// These types must implement the `Sync` trait
     pub sync_types: SyncTypes,
+
+    /// What the body of each context does, keyed by context; `extern` tasks have no entry and
+    /// the variants of a task share one
+    pub bodies: Bodies,
+
+    /// Software tasks spawned from the user code of the `#[app]` module, outside the contexts
+    pub user_code_spawns: Set<Task>,
+
+    /// Worst-case response times of the tasks that have `wcet` and `period` / `deadline`
+    /// annotations
+    pub response_times: ResponseTimes,
+
+    /// Lints that fired at the `Warn` level
+    ///
+    /// The backend should emit them, e.g. by expanding each one with its `ToTokens`
+    /// implementation
+    pub warnings: Vec<Warning>,
 }
 
-/// All channels, keyed by dispatch priority
-pub type Channels = BTreeMap<Priority, Channel>;
+/// All channels, keyed by core and then by dispatch priority
+pub type Channels = BTreeMap<Core, BTreeMap<Priority, Channel>>;
+
+/// Dispatcher interrupts, keyed by core and then by dispatch priority
+pub type Dispatchers = BTreeMap<Core, BTreeMap<Priority, Ident>>;
+
+/// The bound vectors, keyed by core and then by interrupt or exception name
+pub type VectorTable = BTreeMap<Core, Map<Vector>>;
+
+/// Priority levels that need an async executor, keyed by core
+pub type AsyncPriorities = BTreeMap<Core, BTreeSet<Priority>>;
+
+/// Location of the shared resources
+pub type Locations = IndexMap<Resource, Location>;
 
 /// Location of all *used* shared resources
 pub type UsedSharedResource = IndexSet<Resource>;
@@ -333,22 +671,99 @@ pub type UsedLocalResource = IndexSet<Resource>;
 /// Resource ownership
 pub type Ownerships = IndexMap<Resource, Ownership>;
 
-/// These types must implement the `Send` trait
-pub type SendTypes = Set<Box<Type>>;
+/// Resource ownership in each configuration, see [`Analysis::gated_ownerships`]
+pub type GatedOwnerships = IndexMap<Resource, Vec<Gated<Ownership>>>;
+
+/// These types must implement the `Send` trait, each one under the condition where it's required
+pub type SendTypes = IndexMap<Box<Type>, Cfg>;
 
 /// These types must implement the `Sync` trait
 pub type SyncTypes = Set<Box<Type>>;
 
+/// The bodies of the contexts, keyed by context
+///
+/// This is the spawn graph of the application: each context points to the software tasks it
+/// spawns
+pub type Bodies = Map<Body>;
+
+/// What a context does with its context argument, found by walking its body
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Body {
+    /// The shared resources the body uses, `cx.shared.<name>`
+    pub shared_resources: Set<Resource>,
+
+    /// The local resources the body uses, `cx.local.<name>`
+    pub local_resources: Set<Resource>,
+
+    /// The software tasks the body spawns, with `<task>::spawn` or a timed variant
+    pub spawns: Set<Task>,
+
+    /// The software tasks the body spawns with a timed variant: `spawn_after`, `spawn_at`
+    pub timed_spawns: Set<Task>,
+
+    /// The context argument is destructured or used other than by naming a resource (e.g. it's
+    /// passed to a function) so the resource uses above may be incomplete
+    pub opaque: bool,
+}
+
+/// An entry of the vector table
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Vector {
+    /// Whether the vector is an interrupt or an exception; dispatchers are always interrupts
+    pub binding: Binding,
+
+    /// What handles the vector, each gated by its `#[cfg]`; there is more than one handler when
+    /// tasks or monotonics under mutually exclusive `#[cfg]`s bind the same vector
+    pub handlers: Vec<Gated<Handler>>,
+}
+
+/// What handles a vector
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Handler {
+    /// A hardware task
+    HardwareTask(Task),
+
+    /// The dispatcher of the software tasks of this priority
+    Dispatcher(Priority),
+
+    /// A monotonic timer
+    Monotonic(Ident),
+}
+
 /// A channel used to send messages
 #[derive(Debug, Default)]
 pub struct Channel {
-    /// The channel capacity
+    /// The channel capacity, in the configuration that needs the most
     pub capacity: u8,
 
+    /// The terms of `capacity`, one per task of the channel, each gated by the `#[cfg]` of its task
+    ///
+    /// The capacity in a given configuration is the sum of the terms whose condition holds
+    pub capacities: Vec<Gated<u8>>,
+
     /// Tasks that can be spawned on this channel
     pub tasks: BTreeSet<Task>,
 }
 
+/// The core(s) a shared resource is accessed from
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Location {
+    /// Accessed from a single core
+    Owned {
+        /// The core that accesses the resource
+        core: Core,
+    },
+
+    /// Accessed from more than one core
+    ///
+    /// Priority based locks can't protect this resource on their own, the backend must also
+    /// synchronize the cores
+    Shared {
+        /// The cores that access the resource
+        cores: BTreeSet<Core>,
+    },
+}
+
 /// Resource ownership
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Ownership {
@@ -358,29 +773,61 @@ pub enum Ownership {
         priority: u8,
     },
 
-    /// "Co-owned" by more than one task; all of them have the same priority
+    /// "Co-owned" by more than one task; none of them can preempt another, because all of them
+    /// have the same priority or because of their preemption thresholds
     CoOwned {
-        /// Priority of the tasks that co-own this resource
+        /// Priority of the tasks that co-own this resource, the highest one if they differ
         priority: u8,
     },
 
-    /// Contended by more than one task; the tasks have different priorities
+    /// Contended by more than one task; the tasks have different priorities or run on different
+    /// cores, in which case the backend must also synchronize the cores (see
+    /// `Analysis::locations`)
     Contended {
-        /// Priority ceiling
+        /// Priority ceiling, the highest priority of all the tasks that access this resource
+        ///
+        /// This is the ceiling of the lock taken for exclusive (`x`) access
+        ceiling: u8,
+
+        /// The highest priority of the tasks with exclusive (`x`) access to this resource, 0 if
+        /// there are none
+        ///
+        /// This is the ceiling of the lock taken for shared (`&x`) access as readers only need
+        /// to exclude the writers
+        read_ceiling: u8,
+    },
+
+    /// Accessed by more than one task, with different priorities, but only through shared
+    /// references (`&x`)
+    ///
+    /// No lock is ever needed, the resource can be used as if it were `#[lock_free]`; its type
+    /// is in `Analysis::sync_types`
+    ReadOnly {
+        /// The highest priority of all the tasks that access this resource
         ceiling: u8,
     },
 }
 
 impl Ownership {
-    /// Whether this resource needs to a lock at this priority level
-    pub fn needs_lock(&self, priority: u8) -> bool {
+    /// Whether this resource needs to a lock at this priority level for this kind of access
+    ///
+    /// `priority` is the priority the task runs at, its preemption threshold if it has one; a
+    /// threshold at or above the ceiling already keeps out all the other users of the resource
+    pub fn needs_lock(&self, priority: u8, access: Access) -> bool {
         match self {
-            Ownership::Owned { .. } | Ownership::CoOwned { .. } => false,
-
-            Ownership::Contended { ceiling } => {
-                debug_assert!(*ceiling >= priority);
+            Ownership::Owned { .. } | Ownership::CoOwned { .. } | Ownership::ReadOnly { .. } => {
+                false
+            }
 
-                priority < *ceiling
+            Ownership::Contended {
+                ceiling,
+                read_ceiling,
+            } => {
+                if access.is_shared() {
+                    priority < *read_ceiling
+                } else {
+                    priority < *ceiling
+                }
             }
         }
     }
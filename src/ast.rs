@@ -1,5 +1,8 @@
 //! Abstract Syntax Tree
 
+use core::{ops::Index, slice, time::Duration};
+
+use proc_macro2::Span;
 use syn::{Attribute, Expr, Ident, Item, ItemUse, Pat, PatType, Path, Stmt, Type};
 
 use crate::Map;
@@ -36,10 +39,124 @@ pub struct App {
     pub user_code: Vec<Item>,
 
     /// Hardware tasks: `#[task(binds = ..)]`s
-    pub hardware_tasks: Map<HardwareTask>,
+    pub hardware_tasks: Tasks<HardwareTask>,
 
     /// Software tasks: `#[task]`
-    pub software_tasks: Map<SoftwareTask>,
+    pub software_tasks: Tasks<SoftwareTask>,
+}
+
+/// Tasks and their names, in declaration order
+///
+/// Unlike a [`Map`], a name can appear more than once: tasks under mutually exclusive `#[cfg]`s
+/// are never compiled together so they can have the same name. Each one is a variant of the task,
+/// so there's no lookup by name that returns a single task: use [`Tasks::variants`]
+#[derive(Debug)]
+pub struct Tasks<T> {
+    tasks: Vec<(Ident, T)>,
+}
+
+impl<T> Tasks<T> {
+    /// Creates an empty list of tasks
+    pub fn new() -> Self {
+        Tasks { tasks: vec![] }
+    }
+
+    /// Adds a task, or another variant of it if `name` is already there
+    pub fn insert(&mut self, name: Ident, task: T) {
+        self.tasks.push((name, task));
+    }
+
+    /// The number of tasks, counting each variant
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Whether there are no tasks
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Whether there's a task called `name`
+    pub fn contains_key(&self, name: &Ident) -> bool {
+        self.tasks.iter().any(|(other, _)| other == name)
+    }
+
+    /// The variants of the task called `name`
+    pub fn variants(&self, name: &Ident) -> impl Iterator<Item = &T> {
+        let name = name.clone();
+        self.iter()
+            .filter(move |(other, _)| **other == name)
+            .map(|(_, task)| task)
+    }
+
+    /// The tasks and their names; a name appears once per variant
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            tasks: self.tasks.iter(),
+        }
+    }
+
+    /// The names of the tasks; a name appears once per variant
+    pub fn keys(&self) -> impl Iterator<Item = &Ident> {
+        self.tasks.iter().map(|(name, _)| name)
+    }
+
+    /// The tasks
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.tasks.iter().map(|(_, task)| task)
+    }
+
+    /// The tasks, mutably
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.tasks.iter_mut().map(|(_, task)| task)
+    }
+
+    /// Keeps only the tasks for which `f` returns `true`
+    pub fn retain(&mut self, mut f: impl FnMut(&Ident, &T) -> bool) {
+        self.tasks.retain(|(name, task)| f(name, task))
+    }
+}
+
+impl<T> Default for Tasks<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The task at position `index`, in declaration order
+///
+/// # Panics
+///
+/// If `index` is out of bounds
+impl<T> Index<usize> for Tasks<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        &self.tasks[index].1
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Tasks<T> {
+    type Item = (&'a Ident, &'a T);
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+/// Iterator over [`Tasks`] and their names
+#[derive(Clone, Debug)]
+pub struct Iter<'a, T> {
+    tasks: slice::Iter<'a, (Ident, T)>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (&'a Ident, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.tasks.next().map(|(name, task)| (name, task))
+    }
 }
 
 /// Interrupts used to dispatch software tasks
@@ -62,8 +179,14 @@ pub struct AppArgs {
     /// Peripherals
     pub peripherals: bool,
 
+    /// Number of cores, 1 unless `cores = N` is given
+    pub cores: u8,
+
     /// Interrupts used to dispatch software tasks
     pub extern_interrupts: ExternInterrupts,
+
+    /// The `[..]` of `dispatchers = [..]`, if given
+    pub dispatchers_span: Option<Span>,
 }
 
 /// The `init`-ialization function
@@ -96,6 +219,9 @@ pub struct Init {
 #[derive(Debug)]
 #[non_exhaustive]
 pub struct InitArgs {
+    /// The core `init` runs on
+    pub core: u8,
+
     /// Local resources that can be accessed from this context
     pub local_resources: LocalResources,
 }
@@ -103,6 +229,7 @@ pub struct InitArgs {
 impl Default for InitArgs {
     fn default() -> Self {
         Self {
+            core: 0,
             local_resources: LocalResources::new(),
         }
     }
@@ -126,12 +253,18 @@ pub struct Idle {
 
     /// The statements that make up this `idle` function
     pub stmts: Vec<Stmt>,
+
+    /// The `idle` function is `async`
+    pub is_async: bool,
 }
 
 /// `idle` context metadata
 #[derive(Debug)]
 #[non_exhaustive]
 pub struct IdleArgs {
+    /// The core `idle` runs on
+    pub core: u8,
+
     /// Local resources that can be accessed from this context
     pub local_resources: LocalResources,
 
@@ -142,6 +275,7 @@ pub struct IdleArgs {
 impl Default for IdleArgs {
     fn default() -> Self {
         Self {
+            core: 0,
             local_resources: LocalResources::new(),
             shared_resources: SharedResources::new(),
         }
@@ -153,6 +287,10 @@ impl Default for IdleArgs {
 pub struct SharedResourceProperties {
     /// A lock free (exclusive resource)
     pub lock_free: bool,
+
+    /// An atomic, or another `Sync` lock-free primitive, that any task can access through a
+    /// shared reference (`&x`) without a lock
+    pub atomic: bool,
 }
 
 /// A shared resource, defined in `#[shared]`
@@ -207,6 +345,9 @@ pub struct Monotonic {
 
     /// Monotonic args
     pub args: MonotonicArgs,
+
+    /// What `args.binds` is
+    pub binding: Binding,
 }
 
 /// Monotonic metadata
@@ -219,6 +360,9 @@ pub struct MonotonicArgs {
     /// The priority of this monotonic
     pub priority: Option<u8>,
 
+    /// The core this monotonic runs on
+    pub core: u8,
+
     /// If this is the default monotonic
     pub default: bool,
 }
@@ -247,6 +391,9 @@ pub struct SoftwareTask {
 
     /// The task is declared externally
     pub is_extern: bool,
+
+    /// The task is an `async` function
+    pub is_async: bool,
 }
 
 /// Software task metadata
@@ -259,6 +406,16 @@ pub struct SoftwareTaskArgs {
     /// The priority of this task
     pub priority: u8,
 
+    /// The preemption threshold of this task: once running, it can only be preempted by tasks
+    /// of a higher priority than this; `None` if not given, which is the same as the priority
+    pub threshold: Option<u8>,
+
+    /// The core this task runs on
+    pub core: u8,
+
+    /// Timing annotations
+    pub timing: Timing,
+
     /// Local resources that can be accessed from this context
     pub local_resources: LocalResources,
 
@@ -271,6 +428,9 @@ impl Default for SoftwareTaskArgs {
         Self {
             capacity: 1,
             priority: 1,
+            threshold: None,
+            core: 0,
+            timing: Timing::default(),
             local_resources: LocalResources::new(),
             shared_resources: SharedResources::new(),
         }
@@ -284,6 +444,9 @@ pub struct HardwareTask {
     /// Hardware task metadata
     pub args: HardwareTaskArgs,
 
+    /// What `args.binds` is
+    pub binding: Binding,
+
     /// `#[cfg]` attributes like `#[cfg(debug_assertions)]`
     pub cfgs: Vec<Attribute>,
 
@@ -300,6 +463,13 @@ pub struct HardwareTask {
     pub is_extern: bool,
 }
 
+impl SoftwareTaskArgs {
+    /// The priority this task runs at once started: its preemption threshold, or its priority
+    pub fn threshold(&self) -> u8 {
+        self.threshold.unwrap_or(self.priority)
+    }
+}
+
 /// Hardware task metadata
 #[derive(Debug)]
 #[non_exhaustive]
@@ -310,6 +480,16 @@ pub struct HardwareTaskArgs {
     /// The priority of this task
     pub priority: u8,
 
+    /// The preemption threshold of this task: once running, it can only be preempted by tasks
+    /// of a higher priority than this; `None` if not given, which is the same as the priority
+    pub threshold: Option<u8>,
+
+    /// The core this task runs on
+    pub core: u8,
+
+    /// Timing annotations
+    pub timing: Timing,
+
     /// Local resources that can be accessed from this context
     pub local_resources: LocalResources,
 
@@ -317,6 +497,53 @@ pub struct HardwareTaskArgs {
     pub shared_resources: SharedResources,
 }
 
+impl HardwareTaskArgs {
+    /// The priority this task runs at once started: its preemption threshold, or its priority
+    pub fn threshold(&self) -> u8 {
+        self.threshold.unwrap_or(self.priority)
+    }
+}
+
+/// The kind of vector a hardware task or monotonic is bound to, according to `Settings::device`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Binding {
+    /// A device specific interrupt; without a device every vector is an interrupt
+    Interrupt,
+
+    /// A core exception whose priority can be configured, e.g. `SysTick`
+    Exception,
+
+    /// A core exception with a priority fixed by the hardware, e.g. `HardFault`
+    FixedPriorityException,
+}
+
+impl Binding {
+    /// Whether this is a core exception
+    pub fn is_exception(&self) -> bool {
+        *self != Binding::Interrupt
+    }
+}
+
+/// Timing annotations of a task: `wcet = 120us, period = 1ms, deadline = 1ms`
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Timing {
+    /// Worst-case execution time
+    pub wcet: Option<Duration>,
+
+    /// Period, or minimum time between two releases of the task
+    pub period: Option<Duration>,
+
+    /// Relative deadline
+    pub deadline: Option<Duration>,
+}
+
+impl Timing {
+    /// The deadline, the period if no `deadline` is given
+    pub fn deadline(&self) -> Option<Duration> {
+        self.deadline.or(self.period)
+    }
+}
+
 /// A `static mut` variable local to and owned by a context
 #[derive(Debug)]
 #[non_exhaustive]
@@ -339,11 +566,35 @@ pub struct Local {
 #[non_exhaustive]
 pub enum TaskLocal {
     /// The local is declared externally (i.e. `#[local]` struct)
-    External,
+    External {
+        /// `#[cfg]` attributes on the list entry, like `#[cfg(feature = "x")] x`
+        cfgs: Vec<Attribute>,
+    },
     /// The local is declared in the task
     Declared(Local),
 }
 
+impl TaskLocal {
+    /// The `#[cfg]` attributes of this entry
+    pub fn cfgs(&self) -> &[Attribute] {
+        match self {
+            TaskLocal::External { cfgs } => cfgs,
+            TaskLocal::Declared(local) => &local.cfgs,
+        }
+    }
+}
+
+/// An entry of a `shared = [..]` list
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct SharedAccess {
+    /// `x` or `&x`
+    pub access: Access,
+
+    /// `#[cfg]` attributes on the entry, like `#[cfg(feature = "x")] x`
+    pub cfgs: Vec<Attribute>,
+}
+
 /// Resource access
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Access {
@@ -367,7 +618,7 @@ impl Access {
 }
 
 /// Shared resource access list in task attribute
-pub type SharedResources = Map<Access>;
+pub type SharedResources = Map<SharedAccess>;
 
 /// Local resource access/declaration list in task attribute
 pub type LocalResources = Map<TaskLocal>;
@@ -0,0 +1,248 @@
+//! Analysis of the bodies of the contexts: resource uses and `spawn` calls
+
+use proc_macro2::{TokenStream as TokenStream2, TokenTree};
+use syn::{
+    visit::{self, Visit},
+    Expr, ExprField, ExprPath, Ident, Macro, Member, Pat, Path, Stmt,
+};
+
+use crate::{
+    analyze::{Bodies, Body},
+    ast::{App, Tasks},
+    Set,
+};
+
+/// Fields of the context that aren't resources: the core and device peripherals and the critical
+/// section token of `init`
+const PLAIN_FIELDS: &[&str] = &["core", "device", "cs"];
+
+fn is_plain_field(field: &Ident) -> bool {
+    PLAIN_FIELDS.iter().any(|plain| field == plain)
+}
+
+/// Walks the bodies of all the contexts, and the user code for `spawn` calls
+pub(crate) fn app(app: &App) -> (Bodies, Set<Ident>) {
+    let tasks = &app.software_tasks;
+    let mut bodies = Bodies::new();
+
+    bodies.insert(
+        app.init.name.clone(),
+        body(&app.init.context, &app.init.stmts, tasks),
+    );
+
+    if let Some(idle) = &app.idle {
+        bodies.insert(idle.name.clone(), body(&idle.context, &idle.stmts, tasks));
+    }
+
+    for (name, task) in &app.hardware_tasks {
+        if !task.is_extern {
+            merge(&mut bodies, name, body(&task.context, &task.stmts, tasks));
+        }
+    }
+
+    for (name, task) in &app.software_tasks {
+        if !task.is_extern {
+            merge(&mut bodies, name, body(&task.context, &task.stmts, tasks));
+        }
+    }
+
+    let mut visitor = Visitor {
+        context: None,
+        tasks,
+        body: Body::default(),
+    };
+    for item in &app.user_code {
+        visitor.visit_item(item);
+    }
+
+    (bodies, visitor.body.spawns)
+}
+
+/// The variants of a task, under mutually exclusive `#[cfg]`s, share an entry
+fn merge(bodies: &mut Bodies, name: &Ident, body: Body) {
+    match bodies.get_mut(name) {
+        Some(merged) => {
+            merged.shared_resources.extend(body.shared_resources);
+            merged.local_resources.extend(body.local_resources);
+            merged.spawns.extend(body.spawns);
+            merged.timed_spawns.extend(body.timed_spawns);
+            merged.opaque |= body.opaque;
+        }
+        None => {
+            bodies.insert(name.clone(), body);
+        }
+    }
+}
+
+fn body<T>(context: &Pat, stmts: &[Stmt], tasks: &Tasks<T>) -> Body {
+    let (context, opaque) = match context {
+        Pat::Ident(pat) if pat.subpat.is_none() => (Some(&pat.ident), false),
+        // `_`: the body can't use any resource
+        Pat::Wild(_) => (None, false),
+        // Destructured context
+        _ => (None, true),
+    };
+
+    let mut visitor = Visitor {
+        context,
+        tasks,
+        body: Body {
+            opaque,
+            ..Body::default()
+        },
+    };
+    for stmt in stmts {
+        visitor.visit_stmt(stmt);
+    }
+
+    visitor.body
+}
+
+struct Visitor<'a, T> {
+    /// The name of the context argument
+    context: Option<&'a Ident>,
+    tasks: &'a Tasks<T>,
+    body: Body,
+}
+
+impl<'a, T> Visitor<'a, T> {
+    fn is_context(&self, path: &Path) -> bool {
+        matches!((self.context, path.get_ident()), (Some(context), Some(ident)) if context == ident)
+    }
+
+    /// Records the spawn if `segments` is `task::spawn`, `task::spawn_after`,
+    /// `task::Mono::spawn_at`, ..
+    fn spawn(&mut self, segments: &[&Ident]) {
+        let (last, rest) = match segments.split_last() {
+            Some(split) => split,
+            None => return,
+        };
+        let timed = if *last == "spawn" {
+            false
+        } else if *last == "spawn_after" || *last == "spawn_at" {
+            true
+        } else {
+            return;
+        };
+
+        if let Some(spawnee) = rest
+            .iter()
+            .rev()
+            .take(2)
+            .find(|segment| self.tasks.contains_key(segment))
+        {
+            self.body.spawns.insert((*spawnee).clone());
+            if timed {
+                self.body.timed_spawns.insert((*spawnee).clone());
+            }
+        }
+    }
+
+    /// The resource uses and spawns in the tokens of a macro invocation, e.g. `cx.shared.x` in
+    /// `defmt::info!("{}", cx.shared.x)`
+    fn visit_tokens(&mut self, tokens: TokenStream2) {
+        let tokens = tokens.into_iter().collect::<Vec<_>>();
+
+        let mut i = 0;
+        while i < tokens.len() {
+            match &tokens[i] {
+                TokenTree::Group(group) => self.visit_tokens(group.stream()),
+
+                TokenTree::Ident(ident) if Some(ident) == self.context => match &tokens[i..] {
+                    [_, TokenTree::Punct(dot1), TokenTree::Ident(kind), TokenTree::Punct(dot2), TokenTree::Ident(name), ..]
+                        if dot1.as_char() == '.' && dot2.as_char() == '.' =>
+                    {
+                        self.resource(kind, name);
+                        i += 4;
+                    }
+                    [_, TokenTree::Punct(dot), TokenTree::Ident(field), ..]
+                        if dot.as_char() == '.' && is_plain_field(field) =>
+                    {
+                        i += 2;
+                    }
+                    _ => self.body.opaque = true,
+                },
+
+                TokenTree::Ident(_) => {
+                    // A path: idents separated by `::`
+                    let mut segments = vec![];
+                    let mut j = i;
+                    while let Some(TokenTree::Ident(ident)) = tokens.get(j) {
+                        segments.push(ident);
+                        match (tokens.get(j + 1), tokens.get(j + 2)) {
+                            (Some(TokenTree::Punct(a)), Some(TokenTree::Punct(b)))
+                                if a.as_char() == ':' && b.as_char() == ':' =>
+                            {
+                                j += 3
+                            }
+                            _ => break,
+                        }
+                    }
+
+                    self.spawn(&segments);
+                    i = j;
+                }
+
+                _ => {}
+            }
+
+            i += 1;
+        }
+    }
+
+    /// `cx.<kind>.<name>`
+    fn resource(&mut self, kind: &Ident, name: &Ident) {
+        if kind == "shared" {
+            self.body.shared_resources.insert(name.clone());
+        } else if kind == "local" {
+            self.body.local_resources.insert(name.clone());
+        } else if !is_plain_field(kind) {
+            self.body.opaque = true;
+        }
+    }
+}
+
+impl<'a, 'ast, T> Visit<'ast> for Visitor<'a, T> {
+    fn visit_expr_field(&mut self, outer: &'ast ExprField) {
+        if let (Expr::Field(inner), Member::Named(name)) = (&*outer.base, &outer.member) {
+            if let (Expr::Path(path), Member::Named(kind)) = (&*inner.base, &inner.member) {
+                if self.is_context(&path.path) {
+                    self.resource(kind, name);
+                    return;
+                }
+            }
+        }
+
+        // `cx.device`, `cx.core`, ..
+        if let (Expr::Path(path), Member::Named(field)) = (&*outer.base, &outer.member) {
+            if self.is_context(&path.path) && is_plain_field(field) {
+                return;
+            }
+        }
+
+        visit::visit_expr_field(self, outer);
+    }
+
+    fn visit_expr_path(&mut self, path: &'ast ExprPath) {
+        // Any other use of the context, e.g. passing it to a function
+        if self.is_context(&path.path) {
+            self.body.opaque = true;
+        }
+
+        let segments = path
+            .path
+            .segments
+            .iter()
+            .map(|segment| &segment.ident)
+            .collect::<Vec<_>>();
+        self.spawn(&segments);
+
+        visit::visit_expr_path(self, path);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        self.visit_tokens(mac.tokens.clone());
+
+        visit::visit_macro(self, mac);
+    }
+}
@@ -0,0 +1,498 @@
+//! `#[cfg]` predicates
+//!
+//! The `#[cfg]` attributes of tasks, resources and monotonics are kept as [`Cfg`] predicates so
+//! the analysis can tell under which configuration each of its results applies, e.g. the
+//! ownership of a resource whose users are only compiled with some feature.
+//!
+//! Host tools can also evaluate the predicates against a [`CfgSet`], through
+//! `Settings::cfg`, to see the application as it's compiled with some features.
+
+use core::fmt;
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, ToTokens};
+use syn::{Attribute, Lit, Meta, NestedMeta};
+
+use crate::ast::{App, LocalResources, SharedResources};
+
+/// Configuration options that have a single value, e.g. there's only one `target_arch`
+const SINGLE_VALUED: &[&str] = &[
+    "target_arch",
+    "target_endian",
+    "target_env",
+    "target_os",
+    "target_pointer_width",
+    "target_vendor",
+    "panic",
+];
+
+/// Satisfiability is only decided for predicates with up to this many options; bigger ones are
+/// assumed to be satisfiable
+const MAX_OPTIONS: usize = 12;
+
+/// The configurations are only split over up to this many distinct `#[cfg]`s
+const MAX_SPLIT: usize = 6;
+
+/// A `#[cfg]` predicate
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Cfg {
+    /// A configuration option: `debug_assertions`, `feature = "radio"`, ..
+    ///
+    /// Predicates this module doesn't understand are kept as an option named after their tokens
+    Option {
+        /// Name of the option
+        name: String,
+
+        /// Value of the option, if it's a key-value option
+        value: Option<String>,
+    },
+
+    /// `all(..)`; `all()` always holds
+    All(Vec<Cfg>),
+
+    /// `any(..)`; `any()` never holds
+    Any(Vec<Cfg>),
+
+    /// `not(..)`
+    Not(Box<Cfg>),
+}
+
+/// A value that only applies when its `cfg` predicate holds
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Gated<T> {
+    /// The condition under which `value` applies
+    pub cfg: Cfg,
+
+    /// The value
+    pub value: T,
+}
+
+impl Cfg {
+    /// The predicate that always holds, `all()`
+    pub fn always() -> Self {
+        Cfg::All(vec![])
+    }
+
+    /// Whether this is `all()`, the predicate of items without `#[cfg]`
+    pub fn is_always(&self) -> bool {
+        matches!(self, Cfg::All(cfgs) if cfgs.is_empty())
+    }
+
+    /// The predicate of an item with the `#[cfg]` attributes `attrs`: all of them must hold
+    pub fn from_attrs(attrs: &[Attribute]) -> Self {
+        attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("cfg"))
+            .map(|attr| match attr.parse_meta() {
+                Ok(Meta::List(list)) if list.nested.len() == 1 => Cfg::from_nested(&list.nested[0]),
+                _ => Cfg::opaque(attr.tokens.clone()),
+            })
+            .fold(Cfg::always(), Cfg::and)
+    }
+
+    fn from_nested(nested: &NestedMeta) -> Self {
+        match nested {
+            NestedMeta::Meta(Meta::Path(path)) if path.get_ident().is_some() => Cfg::Option {
+                name: path.get_ident().unwrap().to_string(),
+                value: None,
+            },
+
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.get_ident().is_some() => {
+                match &nv.lit {
+                    Lit::Str(value) => Cfg::Option {
+                        name: nv.path.get_ident().unwrap().to_string(),
+                        value: Some(value.value()),
+                    },
+                    _ => Cfg::opaque(nested.to_token_stream()),
+                }
+            }
+
+            NestedMeta::Meta(Meta::List(list)) => {
+                let cfgs = list.nested.iter().map(Cfg::from_nested).collect();
+                if list.path.is_ident("all") {
+                    Cfg::All(cfgs)
+                } else if list.path.is_ident("any") {
+                    Cfg::Any(cfgs)
+                } else if list.path.is_ident("not") && list.nested.len() == 1 {
+                    Cfg::Not(Box::new(Cfg::from_nested(&list.nested[0])))
+                } else {
+                    Cfg::opaque(nested.to_token_stream())
+                }
+            }
+
+            _ => Cfg::opaque(nested.to_token_stream()),
+        }
+    }
+
+    fn opaque(tokens: TokenStream2) -> Self {
+        Cfg::Option {
+            name: tokens.to_string(),
+            value: None,
+        }
+    }
+
+    /// `all(self, other)`
+    pub fn and(self, other: Cfg) -> Self {
+        match (self, other) {
+            (cfg, always) | (always, cfg) if always.is_always() => cfg,
+            (Cfg::All(mut cfgs), Cfg::All(others)) => {
+                cfgs.extend(others);
+                Cfg::All(cfgs)
+            }
+            (Cfg::All(mut cfgs), cfg) | (cfg, Cfg::All(mut cfgs)) => {
+                cfgs.push(cfg);
+                Cfg::All(cfgs)
+            }
+            (a, b) if a == b => a,
+            (a, b) => Cfg::All(vec![a, b]),
+        }
+    }
+
+    /// `any(self, other)`
+    pub fn or(self, other: Cfg) -> Self {
+        match (self, other) {
+            (_, always) | (always, _) if always.is_always() => always,
+            (Cfg::Any(mut cfgs), Cfg::Any(others)) => {
+                cfgs.extend(others);
+                Cfg::Any(cfgs)
+            }
+            (Cfg::Any(mut cfgs), cfg) | (cfg, Cfg::Any(mut cfgs)) => {
+                cfgs.push(cfg);
+                Cfg::Any(cfgs)
+            }
+            (a, b) if a == b => a,
+            (a, b) => Cfg::Any(vec![a, b]),
+        }
+    }
+
+    /// `not(self)`
+    pub fn negate(self) -> Self {
+        match self {
+            Cfg::Not(cfg) => *cfg,
+            cfg => Cfg::Not(Box::new(cfg)),
+        }
+    }
+
+    /// Evaluates this predicate; `option` tells whether the option `name` (`= value`) is set
+    pub fn eval(&self, option: &impl Fn(&str, Option<&str>) -> bool) -> bool {
+        match self {
+            Cfg::Option { name, value } => option(name, value.as_deref()),
+            Cfg::All(cfgs) => cfgs.iter().all(|cfg| cfg.eval(option)),
+            Cfg::Any(cfgs) => cfgs.iter().any(|cfg| cfg.eval(option)),
+            Cfg::Not(cfg) => !cfg.eval(option),
+        }
+    }
+
+    /// Whether some configuration satisfies this predicate
+    ///
+    /// Options are independent of each other, except for the ones that only have a single value
+    /// (`target_arch`, `target_os`, ..)
+    pub fn is_satisfiable(&self) -> bool {
+        let mut options = vec![];
+        self.options(&mut options);
+        if options.len() > MAX_OPTIONS {
+            return true;
+        }
+
+        (0..1u32 << options.len()).any(|set| {
+            let is_set = |i: usize| set & (1 << i) != 0;
+
+            // At most one value of the single-valued options
+            let consistent = options.iter().enumerate().all(|(i, (name, value))| {
+                !is_set(i)
+                    || value.is_none()
+                    || !SINGLE_VALUED.contains(&name.as_str())
+                    || options[..i]
+                        .iter()
+                        .enumerate()
+                        .all(|(j, (other, other_value))| {
+                            !is_set(j) || other != name || other_value.is_none()
+                        })
+            });
+
+            consistent
+                && self.eval(&|name, value| {
+                    options
+                        .iter()
+                        .position(|(n, v)| n == name && v.as_deref() == value)
+                        .map(is_set)
+                        .unwrap_or(false)
+                })
+        })
+    }
+
+    /// Whether every configuration that satisfies this predicate also satisfies `other`
+    pub fn implies(&self, other: &Cfg) -> bool {
+        !self.clone().and(other.clone().negate()).is_satisfiable()
+    }
+
+    /// Whether this predicate and `other` can hold at the same time, i.e. they are not mutually
+    /// exclusive
+    pub fn overlaps(&self, other: &Cfg) -> bool {
+        self.clone().and(other.clone()).is_satisfiable()
+    }
+
+    /// The distinct options of this predicate
+    fn options(&self, options: &mut Vec<(String, Option<String>)>) {
+        match self {
+            Cfg::Option { name, value } => {
+                let option = (name.clone(), value.clone());
+                if !options.contains(&option) {
+                    options.push(option);
+                }
+            }
+            Cfg::All(cfgs) | Cfg::Any(cfgs) => cfgs.iter().for_each(|cfg| cfg.options(options)),
+            Cfg::Not(cfg) => cfg.options(options),
+        }
+    }
+}
+
+/// The active configuration options, what rustc evaluates `#[cfg]` against: `feature = "radio"`,
+/// `target_arch = "arm"`, `debug_assertions`, ..
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CfgSet {
+    options: Vec<(String, Option<String>)>,
+}
+
+/// A malformed option given to [`CfgSet::insert_spec`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpecError {
+    /// The option
+    pub spec: String,
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` is not a cfg option, expected `name` or `name=\"value\"`",
+            self.spec
+        )
+    }
+}
+
+impl std::error::Error for SpecError {}
+
+impl CfgSet {
+    /// Sets the option `name`, or `name = "value"`
+    pub fn insert(&mut self, name: &str, value: Option<&str>) {
+        if !self.contains(name, value) {
+            self.options
+                .push((name.to_string(), value.map(|value| value.to_string())));
+        }
+    }
+
+    /// Sets `feature = "name"`
+    pub fn insert_feature(&mut self, name: &str) {
+        self.insert("feature", Some(name));
+    }
+
+    /// Sets an option written like rustc's `--cfg`: `name` or `name="value"`
+    pub fn insert_spec(&mut self, spec: &str) -> Result<(), SpecError> {
+        let error = || SpecError {
+            spec: spec.to_string(),
+        };
+
+        match syn::parse_str::<Meta>(spec).map_err(|_| error())? {
+            Meta::Path(path) => {
+                let name = path.get_ident().ok_or_else(error)?;
+                self.insert(&name.to_string(), None);
+            }
+            Meta::NameValue(nv) => match (nv.path.get_ident(), &nv.lit) {
+                (Some(name), Lit::Str(value)) => {
+                    self.insert(&name.to_string(), Some(&value.value()))
+                }
+                _ => return Err(error()),
+            },
+            Meta::List(_) => return Err(error()),
+        }
+
+        Ok(())
+    }
+
+    /// Whether the option `name`, or `name = "value"`, is set
+    pub fn contains(&self, name: &str, value: Option<&str>) -> bool {
+        self.options
+            .iter()
+            .any(|(n, v)| n == name && v.as_deref() == value)
+    }
+
+    /// Whether `cfg` holds with these options
+    pub fn holds(&self, cfg: &Cfg) -> bool {
+        cfg.eval(&|name, value| self.contains(name, value))
+    }
+
+    fn enables(&self, attrs: &[Attribute]) -> bool {
+        self.holds(&Cfg::from_attrs(attrs))
+    }
+}
+
+/// Removes the tasks, resources, monotonics, declared locals and resource list entries whose
+/// `#[cfg]` doesn't hold
+pub(crate) fn prune(app: &mut App, set: &CfgSet) {
+    app.shared_resources.retain(|_, res| set.enables(&res.cfgs));
+    app.local_resources.retain(|_, res| set.enables(&res.cfgs));
+    app.monotonics
+        .retain(|_, monotonic| set.enables(&monotonic.cfgs));
+    app.hardware_tasks.retain(|_, task| set.enables(&task.cfgs));
+    app.software_tasks.retain(|_, task| set.enables(&task.cfgs));
+
+    let locals = |local_resources: &mut LocalResources| {
+        local_resources.retain(|_, local| set.enables(local.cfgs()))
+    };
+    let shared = |shared_resources: &mut SharedResources| {
+        shared_resources.retain(|_, access| set.enables(&access.cfgs))
+    };
+    locals(&mut app.init.args.local_resources);
+    if let Some(idle) = &mut app.idle {
+        locals(&mut idle.args.local_resources);
+        shared(&mut idle.args.shared_resources);
+    }
+    for task in app.hardware_tasks.values_mut() {
+        locals(&mut task.args.local_resources);
+        shared(&mut task.args.shared_resources);
+    }
+    for task in app.software_tasks.values_mut() {
+        locals(&mut task.args.local_resources);
+        shared(&mut task.args.shared_resources);
+    }
+}
+
+impl Default for Cfg {
+    fn default() -> Self {
+        Cfg::always()
+    }
+}
+
+/// Prints the predicate, e.g. `all(feature = "radio", not(debug_assertions))`, to be used as
+/// `#[cfg(#predicate)]`
+impl ToTokens for Cfg {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        tokens.extend(match self {
+            Cfg::Option { name, value } => {
+                let name = name
+                    .parse::<TokenStream2>()
+                    .unwrap_or_else(|_| quote!(unknown));
+                match value {
+                    Some(value) => quote!(#name = #value),
+                    None => name,
+                }
+            }
+            Cfg::All(cfgs) => quote!(all(#(#cfgs),*)),
+            Cfg::Any(cfgs) => quote!(any(#(#cfgs),*)),
+            Cfg::Not(cfg) => quote!(not(#cfg)),
+        });
+    }
+}
+
+impl fmt::Display for Cfg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |f: &mut fmt::Formatter<'_>, name, cfgs: &[Cfg]| {
+            let cfgs = cfgs.iter().map(|cfg| cfg.to_string()).collect::<Vec<_>>();
+            write!(f, "{}({})", name, cfgs.join(", "))
+        };
+
+        match self {
+            Cfg::Option { name, value: None } => f.write_str(name),
+            Cfg::Option {
+                name,
+                value: Some(value),
+            } => write!(f, "{} = {:?}", name, value),
+            Cfg::All(cfgs) => list(f, "all", cfgs),
+            Cfg::Any(cfgs) => list(f, "any", cfgs),
+            Cfg::Not(cfg) => write!(f, "not({})", cfg),
+        }
+    }
+}
+
+/// Splits the configurations where the `cfgs` hold, or not, into the ones that give the same
+/// value; `value` gets which of the `cfgs` hold and returns `None` if there's nothing to gate
+///
+/// The conditions of the returned values are mutually exclusive
+pub(crate) fn split<T: PartialEq>(
+    cfgs: &[Cfg],
+    value: impl Fn(&[bool]) -> Option<T>,
+) -> Vec<Gated<T>> {
+    // Too many combinations, keep the value of the configuration where all the `cfgs` hold
+    if cfgs.len() > MAX_SPLIT {
+        return value(&vec![true; cfgs.len()])
+            .map(|value| Gated {
+                cfg: Cfg::always(),
+                value,
+            })
+            .into_iter()
+            .collect();
+    }
+
+    // Sets of terms, one set per value; a term is a conjunction of `cfgs[i]`
+    // (`Some(true)`), `not(cfgs[i])` (`Some(false)`) or neither (`None`)
+    let mut groups: Vec<(T, Vec<Vec<Option<bool>>>)> = vec![];
+    for set in 0..1u32 << cfgs.len() {
+        let holds = (0..cfgs.len())
+            .map(|i| set & (1 << i) != 0)
+            .collect::<Vec<_>>();
+        if !term(cfgs, &holds.iter().map(|b| Some(*b)).collect::<Vec<_>>()).is_satisfiable() {
+            continue;
+        }
+
+        if let Some(value) = value(&holds) {
+            let term = holds.into_iter().map(Some).collect();
+            match groups.iter_mut().find(|(other, _)| *other == value) {
+                Some((_, terms)) => terms.push(term),
+                None => groups.push((value, vec![term])),
+            }
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(value, terms)| Gated {
+            cfg: merge(terms)
+                .iter()
+                .map(|term| self::term(cfgs, term))
+                .reduce(Cfg::or)
+                .unwrap_or_default(),
+            value,
+        })
+        .collect()
+}
+
+/// Merges the pairs of terms that only differ in one condition, until there are none left
+fn merge(mut terms: Vec<Vec<Option<bool>>>) -> Vec<Vec<Option<bool>>> {
+    loop {
+        let pair = terms.iter().enumerate().find_map(|(i, a)| {
+            terms[i + 1..].iter().enumerate().find_map(|(j, b)| {
+                let differences = a
+                    .iter()
+                    .zip(b)
+                    .enumerate()
+                    .filter(|(_, (a, b))| a != b)
+                    .collect::<Vec<_>>();
+
+                match &differences[..] {
+                    [(k, (Some(_), Some(_)))] => Some((i, i + 1 + j, *k)),
+                    _ => None,
+                }
+            })
+        });
+
+        match pair {
+            Some((i, j, k)) => {
+                terms.remove(j);
+                terms[i][k] = None;
+            }
+            None => return terms,
+        }
+    }
+}
+
+fn term(cfgs: &[Cfg], term: &[Option<bool>]) -> Cfg {
+    cfgs.iter()
+        .zip(term)
+        .filter_map(|(cfg, holds)| match holds {
+            Some(true) => Some(cfg.clone()),
+            Some(false) => Some(cfg.clone().negate()),
+            None => None,
+        })
+        .fold(Cfg::always(), Cfg::and)
+}
@@ -1,66 +1,157 @@
-use std::collections::HashSet;
+use std::{
+    cmp,
+    collections::{BTreeSet, HashSet},
+};
 
+use proc_macro2::Span;
 use syn::parse;
 
-use crate::ast::App;
+use crate::{ast::App, device, Settings};
+
+pub fn app(app: &App, settings: &Settings) -> parse::Result<()> {
+    let mut errors = vec![];
 
-pub fn app(app: &App) -> parse::Result<()> {
     // Check that all referenced resources have been declared
     // Check that resources are NOT `Exclusive`-ly shared
     let mut owners = HashSet::new();
-    for (_, name, access) in app.shared_resource_accesses() {
+    for (name, user) in app.shared_resource_accesses() {
         if app.shared_resources.get(name).is_none() {
-            return Err(parse::Error::new(
+            errors.push(parse::Error::new(
                 name.span(),
                 "this shared resource has NOT been declared",
             ));
         }
 
-        if access.is_exclusive() {
+        if user.access.is_exclusive() {
             owners.insert(name);
+
+            if matches!(app.shared_resources.get(name), Some(res) if res.properties.atomic) {
+                errors.push(parse::Error::new(
+                    name.span(),
+                    format!(
+                        "`#[atomic]` shared resources can only be accessed through a shared \
+                         reference: `&{}`",
+                        name
+                    ),
+                ));
+            }
         }
     }
 
     for name in app.local_resource_accesses() {
         if app.local_resources.get(name).is_none() {
-            return Err(parse::Error::new(
+            errors.push(parse::Error::new(
                 name.span(),
                 "this local resource has NOT been declared",
             ));
         }
     }
 
-    // Check that no resource has both types of access (`Exclusive` & `Shared`)
-    let exclusive_accesses = app
-        .shared_resource_accesses()
-        .filter_map(|(priority, name, access)| {
-            if priority.is_some() && access.is_exclusive() {
-                Some(name)
-            } else {
-                None
-            }
-        })
-        .collect::<HashSet<_>>();
-    for (_, name, access) in app.shared_resource_accesses() {
-        if access.is_shared() && exclusive_accesses.contains(name) {
-            return Err(parse::Error::new(
-                name.span(),
-                "this implementation doesn't support shared (`&-`) - exclusive (`&mut-`) locks; use `x` instead of `&x`",
-            ));
-        }
-    }
-
     // check that external interrupts are not used as hardware tasks
     for task in app.hardware_tasks.values() {
         let binds = &task.args.binds;
 
         if app.args.extern_interrupts.contains_key(binds) {
-            return Err(parse::Error::new(
+            errors.push(parse::Error::new(
                 binds.span(),
                 "dispatcher interrupts can't be used as hardware tasks",
             ));
         }
     }
 
-    Ok(())
+    // Check that every context is placed on an existing core
+    let cores = app.args.cores;
+    let placements = Some((&app.init.name, app.init.args.core))
+        .into_iter()
+        .chain(app.idle.iter().map(|idle| (&idle.name, idle.args.core)))
+        .chain(
+            app.monotonics
+                .values()
+                .map(|monotonic| (&monotonic.ident, monotonic.args.core)),
+        )
+        .chain(
+            app.hardware_tasks
+                .iter()
+                .map(|(name, task)| (name, task.args.core)),
+        )
+        .chain(
+            app.software_tasks
+                .iter()
+                .map(|(name, task)| (name, task.args.core)),
+        );
+    for (name, core) in placements {
+        if core >= cores {
+            errors.push(parse::Error::new(
+                name.span(),
+                format!(
+                    "core {} doesn't exist, this application has {} core(s) (`cores = {}`)",
+                    core, cores, cores
+                ),
+            ));
+        }
+    }
+
+    // Check that lock free resources are not accessed from more than one core, unless they are
+    // read-only and so `Sync`
+    for (name, resource) in &app.shared_resources {
+        if resource.properties.lock_free && !app.is_read_only(name) {
+            let accessors = app
+                .shared_resource_users(name)
+                .map(|user| user.core)
+                .collect::<BTreeSet<_>>();
+
+            if accessors.len() > 1 {
+                errors.push(parse::Error::new(
+                    name.span(),
+                    format!(
+                        "Lock free shared resource {:?} is used by tasks on different cores",
+                        name.to_string(),
+                    ),
+                ));
+            }
+        }
+    }
+
+    // Check that there's one dispatcher per software task priority level (of each core)
+    let priorities = app
+        .software_tasks
+        .values()
+        .map(|task| (task.args.core, task.args.priority))
+        .collect::<BTreeSet<_>>();
+    let dispatchers = app.args.extern_interrupts.len();
+    if priorities.len() > dispatchers {
+        // Dispatchers are assigned core by core, starting from the highest priority
+        let mut order = priorities.iter().collect::<Vec<_>>();
+        order.sort_by_key(|(core, priority)| (*core, cmp::Reverse(*priority)));
+        let mut missing = order.split_off(dispatchers);
+        missing.sort();
+
+        let missing = missing
+            .iter()
+            .map(|(core, priority)| {
+                if cores == 1 {
+                    priority.to_string()
+                } else {
+                    format!("{} (core {})", priority, core)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        errors.push(parse::Error::new(
+            app.args.dispatchers_span.unwrap_or_else(Span::call_site),
+            format!(
+                "not enough dispatchers for the software tasks (need: {}; given: {}); \
+                 priority level(s) {} have no dispatcher, add more interrupts to `dispatchers = [..]`",
+                priorities.len(),
+                dispatchers,
+                missing.join(", "),
+            ),
+        ));
+    }
+
+    if let Some(device) = &settings.device {
+        errors.extend(device::app(app, device));
+    }
+
+    crate::combine_errors(errors)
 }
@@ -0,0 +1,322 @@
+//! Device model: the interrupts and core exceptions of the target device
+//!
+//! When `Settings::device` is set, the `binds` of the hardware tasks and monotonics are classified
+//! as interrupts or exceptions (see [`Binding`]) and, along with the `dispatchers`, checked
+//! against it. A device is described in a text file, one entry per line; empty lines and `#`
+//! comments are ignored. Exceptions whose priority can't be configured are marked `fixed`:
+//!
+//! ``` text
+//! # Cortex-M core exceptions
+//! exception HardFault fixed
+//! exception SysTick
+//! exception PendSV
+//!
+//! # Device specific interrupts
+//! interrupt USART1
+//! interrupt EXTI0
+//! ```
+//!
+//! or, with the `serde` feature, in JSON:
+//! `{ "interrupts": ["USART1"], "exceptions": ["HardFault", "SysTick"], "fixed_priority": ["HardFault"] }`
+//!
+//! A device without interrupts, like [`Device::cortex_m`], only classifies the bindings: the
+//! names that are not exceptions are assumed to be interrupts and not checked.
+
+use core::fmt;
+
+use syn::{parse, Ident};
+
+use crate::{
+    ast::{App, Binding},
+    Settings,
+};
+
+/// The interrupts and core exceptions of a device
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+pub struct Device {
+    /// Device specific interrupts; these can be bound to tasks or used as dispatchers
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub interrupts: Vec<String>,
+
+    /// Core exceptions; these can be bound to tasks but not used as dispatchers
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub exceptions: Vec<String>,
+
+    /// The core exceptions, among `exceptions`, whose priority is fixed by the hardware
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fixed_priority: Vec<String>,
+}
+
+/// An error in a device description
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// A line that's not `interrupt NAME`, `exception NAME` or `exception NAME fixed`
+    Malformed {
+        /// Line number, starting at 1
+        line: usize,
+    },
+
+    /// A name that appears more than once
+    Duplicate {
+        /// Line number, starting at 1
+        line: usize,
+        /// The repeated name
+        name: String,
+    },
+
+    /// Invalid JSON
+    #[cfg(feature = "serde")]
+    Json(String),
+
+    /// A `fixed_priority` name that's not among the `exceptions`
+    #[cfg(feature = "serde")]
+    NotAnException {
+        /// The name
+        name: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Malformed { line } => write!(
+                f,
+                "line {}: expected `interrupt NAME` or `exception NAME [fixed]`",
+                line
+            ),
+            Error::Duplicate { line, name } => {
+                write!(f, "line {}: `{}` appears more than once", line, name)
+            }
+            #[cfg(feature = "serde")]
+            Error::Json(e) => write!(f, "invalid JSON: {}", e),
+            #[cfg(feature = "serde")]
+            Error::NotAnException { name } => write!(
+                f,
+                "`{}` has a fixed priority but isn't listed in `exceptions`",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Device {
+    /// Parses the text description of a device
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let mut device = Device::default();
+
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let (kind, name, fixed) = match (words.next(), words.next(), words.next(), words.next())
+            {
+                (Some(kind), Some(name), None, None) => (kind, name, false),
+                (Some("exception"), Some(name), Some("fixed"), None) => ("exception", name, true),
+                _ => return Err(Error::Malformed { line: line_number }),
+            };
+
+            if device.contains(name) {
+                return Err(Error::Duplicate {
+                    line: line_number,
+                    name: name.to_string(),
+                });
+            }
+
+            match kind {
+                "interrupt" => device.interrupts.push(name.to_string()),
+                "exception" => device.exceptions.push(name.to_string()),
+                _ => return Err(Error::Malformed { line: line_number }),
+            }
+
+            if fixed {
+                device.fixed_priority.push(name.to_string());
+            }
+        }
+
+        Ok(device)
+    }
+
+    /// The core exceptions of the ARMv6-M, ARMv7-M and ARMv8-M architectures, without any device
+    /// interrupt
+    pub fn cortex_m() -> Self {
+        let exceptions = [
+            "NonMaskableInt",
+            "HardFault",
+            "MemoryManagement",
+            "BusFault",
+            "UsageFault",
+            "SecureFault",
+            "SVCall",
+            "DebugMonitor",
+            "PendSV",
+            "SysTick",
+        ];
+
+        Device {
+            interrupts: vec![],
+            exceptions: exceptions.iter().map(|name| name.to_string()).collect(),
+            fixed_priority: vec!["NonMaskableInt".to_string(), "HardFault".to_string()],
+        }
+    }
+
+    /// Parses the JSON description of a device
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let device: Self = serde_json::from_str(json).map_err(|e| Error::Json(e.to_string()))?;
+
+        if let Some(name) = device
+            .fixed_priority
+            .iter()
+            .find(|name| !device.is_exception(name))
+        {
+            return Err(Error::NotAnException { name: name.clone() });
+        }
+
+        Ok(device)
+    }
+
+    /// Whether `name` is an interrupt or an exception of this device
+    pub fn contains(&self, name: &str) -> bool {
+        self.is_interrupt(name) || self.is_exception(name)
+    }
+
+    /// Whether `name` is an interrupt of this device
+    pub fn is_interrupt(&self, name: &str) -> bool {
+        self.interrupts.iter().any(|interrupt| interrupt == name)
+    }
+
+    /// Whether `name` is a core exception of this device
+    pub fn is_exception(&self, name: &str) -> bool {
+        self.exceptions.iter().any(|exception| exception == name)
+    }
+
+    /// Whether `name` is a core exception with a fixed priority
+    pub fn is_fixed_priority(&self, name: &str) -> bool {
+        self.fixed_priority
+            .iter()
+            .any(|exception| exception == name)
+    }
+
+    /// Classifies the vector `name`; anything that's not an exception is an interrupt
+    pub fn binding(&self, name: &str) -> Binding {
+        if self.is_fixed_priority(name) {
+            Binding::FixedPriorityException
+        } else if self.is_exception(name) {
+            Binding::Exception
+        } else {
+            Binding::Interrupt
+        }
+    }
+
+    /// Whether the interrupts of this device are known, and names can be checked against them
+    fn knows_interrupts(&self) -> bool {
+        !self.interrupts.is_empty()
+    }
+
+    /// The interrupt, or exception if `exceptions` is set, whose name is closest to `name`, if
+    /// any is close enough to be a likely typo
+    fn suggestion(&self, name: &str, exceptions: bool) -> Option<&str> {
+        let max_distance = (name.len() / 3).max(1);
+        let exceptions = if exceptions {
+            &self.exceptions[..]
+        } else {
+            &[]
+        };
+
+        self.interrupts
+            .iter()
+            .chain(exceptions)
+            .map(|candidate| (distance(name, candidate), candidate))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, candidate)| &**candidate)
+    }
+
+    fn unknown(&self, name: &Ident, exceptions: bool) -> parse::Error {
+        let name_s = name.to_string();
+        let what = if exceptions {
+            "an interrupt or exception"
+        } else {
+            "an interrupt"
+        };
+        let mut message = format!("`{}` is not {} of the device", name_s, what);
+        if let Some(suggestion) = self.suggestion(&name_s, exceptions) {
+            message.push_str(&format!("; did you mean `{}`?", suggestion));
+        }
+
+        parse::Error::new(name.span(), message)
+    }
+}
+
+/// Levenshtein distance, case insensitive
+fn distance(a: &str, b: &str) -> usize {
+    let a = a.to_lowercase().chars().collect::<Vec<_>>();
+    let b = b.to_lowercase().chars().collect::<Vec<_>>();
+
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Classifies the vector `binds` according to `settings.device`
+pub(crate) fn binding(binds: &Ident, settings: &Settings) -> Binding {
+    settings
+        .device
+        .as_ref()
+        .map(|device| device.binding(&binds.to_string()))
+        .unwrap_or(Binding::Interrupt)
+}
+
+/// Checks the `binds` and `dispatchers` of `app` against `device`
+pub(crate) fn app(app: &App, device: &Device) -> Vec<parse::Error> {
+    let mut errors = vec![];
+
+    let binds = app
+        .hardware_tasks
+        .values()
+        .map(|task| &task.args.binds)
+        .chain(
+            app.monotonics
+                .values()
+                .map(|monotonic| &monotonic.args.binds),
+        );
+    for name in binds {
+        if device.knows_interrupts() && !device.contains(&name.to_string()) {
+            errors.push(device.unknown(name, true));
+        }
+    }
+
+    for name in app.args.extern_interrupts.keys() {
+        let name_s = name.to_string();
+
+        if device.is_exception(&name_s) {
+            errors.push(parse::Error::new(
+                name.span(),
+                format!(
+                    "`{}` is a core exception, only device interrupts can be used as dispatchers",
+                    name_s
+                ),
+            ));
+        } else if device.knows_interrupts() && !device.is_interrupt(&name_s) {
+            errors.push(device.unknown(name, false));
+        }
+    }
+
+    errors
+}
@@ -0,0 +1,253 @@
+use std::{collections::BTreeMap, fmt::Write as _};
+
+use quote::ToTokens;
+use syn::Ident;
+
+use crate::{
+    analyze::{Analysis, Core, Ownership, Priority},
+    ast::{Access, App, LocalResources, SharedResources, TaskLocal},
+};
+
+impl Analysis {
+    /// Renders the task / resource access graph of `app` in the Graphviz DOT language
+    ///
+    /// Tasks are clustered by priority, and by core in multicore applications. Edges go from a
+    /// task to the shared and local resources it accesses, and to the software tasks it spawns.
+    /// Contended shared resources are annotated with their ceiling(s), and resources that no task
+    /// accesses are drawn dotted
+    pub fn to_dot(&self, app: &App) -> String {
+        let mut out = String::new();
+
+        writeln!(out, "digraph {} {{", quote(&app.name.to_string())).unwrap();
+        writeln!(out, "    rankdir=LR;").unwrap();
+        writeln!(out, "    node [fontname=\"monospace\"];").unwrap();
+
+        // Tasks, clustered by core and priority
+        let mut levels: BTreeMap<(Core, Priority), Vec<(&Ident, String)>> = BTreeMap::new();
+        levels
+            .entry((app.init.args.core, 0))
+            .or_default()
+            .push((&app.init.name, "init".to_string()));
+        if let Some(idle) = &app.idle {
+            levels
+                .entry((idle.args.core, 0))
+                .or_default()
+                .push((&idle.name, "idle".to_string()));
+        }
+        for (name, task) in &app.hardware_tasks {
+            levels
+                .entry((task.args.core, task.args.priority))
+                .or_default()
+                .push((name, format!("binds {}", task.args.binds)));
+        }
+        for (name, task) in &app.software_tasks {
+            let kind = if task.is_async {
+                "async software task"
+            } else {
+                "software task"
+            };
+            levels
+                .entry((task.args.core, task.args.priority))
+                .or_default()
+                .push((name, kind.to_string()));
+        }
+
+        for ((core, priority), tasks) in &levels {
+            writeln!(out).unwrap();
+            if app.args.cores == 1 {
+                writeln!(out, "    subgraph cluster_priority_{} {{", priority).unwrap();
+                writeln!(
+                    out,
+                    "        label={};",
+                    quote(&format!("priority {}", priority))
+                )
+                .unwrap();
+            } else {
+                writeln!(
+                    out,
+                    "    subgraph cluster_core_{}_priority_{} {{",
+                    core, priority
+                )
+                .unwrap();
+                writeln!(
+                    out,
+                    "        label={};",
+                    quote(&format!("core {}, priority {}", core, priority))
+                )
+                .unwrap();
+            }
+            for (name, kind) in tasks {
+                writeln!(
+                    out,
+                    "        {} [label={}, shape=box];",
+                    task_node(name),
+                    quote(&format!("{}\\n({})", name, kind)),
+                )
+                .unwrap();
+            }
+            writeln!(out, "    }}").unwrap();
+        }
+
+        // Shared resources
+        if !app.shared_resources.is_empty() {
+            writeln!(out).unwrap();
+        }
+        for (name, res) in &app.shared_resources {
+            let mut label = format!("{}: {}", name, res.ty.to_token_stream());
+            match self.ownerships.get(name) {
+                Some(Ownership::Contended {
+                    ceiling,
+                    read_ceiling,
+                }) => {
+                    write!(label, "\\nceiling {}", ceiling).unwrap();
+                    if read_ceiling != ceiling {
+                        write!(label, ", read ceiling {}", read_ceiling).unwrap();
+                    }
+                }
+                Some(Ownership::ReadOnly { .. }) => label.push_str("\\nread-only"),
+                _ => {}
+            }
+            if res.properties.lock_free {
+                label.push_str("\\n#[lock_free]");
+            }
+            if res.properties.atomic {
+                label.push_str("\\n#[atomic]");
+            }
+
+            writeln!(
+                out,
+                "    {} [label={}, shape=ellipse{}];",
+                shared_node(name),
+                quote(&label),
+                if self.shared_resources.contains(name) {
+                    ""
+                } else {
+                    ", style=dotted"
+                },
+            )
+            .unwrap();
+        }
+
+        // Local resources, both the `#[local]` ones and the ones declared in task attributes
+        if !app.local_resources.is_empty() {
+            writeln!(out).unwrap();
+        }
+        for (name, res) in &app.local_resources {
+            writeln!(
+                out,
+                "    {} [label={}, shape=component{}];",
+                local_node(None, name),
+                quote(&format!("{}: {}", name, res.ty.to_token_stream())),
+                if self.local_resources.contains(name) {
+                    ""
+                } else {
+                    ", style=dotted"
+                },
+            )
+            .unwrap();
+        }
+        for (task, name, local) in app.declared_local_resources() {
+            writeln!(
+                out,
+                "    {} [label={}, shape=component];",
+                local_node(Some(task), name),
+                quote(&format!("{}: {}", name, local.ty.to_token_stream())),
+            )
+            .unwrap();
+        }
+
+        // Accesses
+        let empty = SharedResources::new();
+        let accesses: Vec<(&Ident, &SharedResources, &LocalResources)> =
+            Some((&app.init.name, &empty, &app.init.args.local_resources))
+                .into_iter()
+                .chain(app.idle.iter().map(|idle| {
+                    (
+                        &idle.name,
+                        &idle.args.shared_resources,
+                        &idle.args.local_resources,
+                    )
+                }))
+                .chain(app.hardware_tasks.iter().map(|(name, task)| {
+                    (
+                        name,
+                        &task.args.shared_resources,
+                        &task.args.local_resources,
+                    )
+                }))
+                .chain(app.software_tasks.iter().map(|(name, task)| {
+                    (
+                        name,
+                        &task.args.shared_resources,
+                        &task.args.local_resources,
+                    )
+                }))
+                .collect();
+
+        writeln!(out).unwrap();
+        for (task, shared, local) in accesses {
+            for (name, access) in shared {
+                let (label, style) = match access.access {
+                    Access::Exclusive => ("exclusive", "solid"),
+                    Access::Shared => ("shared", "dashed"),
+                };
+
+                writeln!(
+                    out,
+                    "    {} -> {} [label={}, style={}];",
+                    task_node(task),
+                    shared_node(name),
+                    quote(label),
+                    style,
+                )
+                .unwrap();
+            }
+
+            for (name, task_local) in local {
+                let node = match task_local {
+                    TaskLocal::External { .. } => local_node(None, name),
+                    TaskLocal::Declared(_) => local_node(Some(task), name),
+                };
+
+                writeln!(out, "    {} -> {};", task_node(task), node).unwrap();
+            }
+        }
+
+        // Spawns
+        for (spawner, body) in &self.bodies {
+            for spawnee in &body.spawns {
+                writeln!(
+                    out,
+                    "    {} -> {} [label=\"spawn\", style=bold];",
+                    task_node(spawner),
+                    task_node(spawnee),
+                )
+                .unwrap();
+            }
+        }
+
+        writeln!(out, "}}").unwrap();
+
+        out
+    }
+}
+
+fn task_node(task: &Ident) -> String {
+    quote(&format!("task {}", task))
+}
+
+fn shared_node(resource: &Ident) -> String {
+    quote(&format!("shared {}", resource))
+}
+
+fn local_node(task: Option<&Ident>, resource: &Ident) -> String {
+    match task {
+        Some(task) => quote(&format!("local {}::{}", task, resource)),
+        None => quote(&format!("local {}", resource)),
+    }
+}
+
+/// Makes `s` a DOT string literal; `\n` escapes already in `s` are kept as line breaks
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\\\""))
+}
@@ -0,0 +1,934 @@
+//! JSON export of the application model, for tooling
+//!
+//! The exported [`Document`] only contains metadata: tasks, resources, their accesses and the
+//! results of the analysis. Function bodies are omitted, types and expressions are rendered as
+//! token strings.
+
+use core::time::Duration;
+
+use proc_macro2::Span;
+use quote::ToTokens;
+use serde::Serialize;
+
+use crate::{
+    analyze::{self, Analysis},
+    ast::{Access, App, Binding, LocalResources, SharedResources, TaskLocal},
+    cfg::Cfg,
+};
+
+/// Version of the exported schema
+///
+/// This is bumped every time the schema changes in a way that's not backwards compatible
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The exported application model
+#[derive(Debug, Serialize)]
+pub struct Document {
+    /// Version of the schema, see [`SCHEMA_VERSION`]
+    pub schema_version: u32,
+
+    /// The parsed application
+    pub app: AppMeta,
+
+    /// The results of the analysis
+    pub analysis: AnalysisMeta,
+}
+
+impl Document {
+    /// Exports `app` and its `analysis`
+    ///
+    /// `file` is the name of the source file that contains the application; it's only used to
+    /// render the locations of the items
+    pub fn new(app: &App, analysis: &Analysis, file: &str) -> Self {
+        Document {
+            schema_version: SCHEMA_VERSION,
+            app: AppMeta::new(app, file),
+            analysis: AnalysisMeta::new(analysis),
+        }
+    }
+
+    /// Renders this document as pretty printed JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("UNREACHABLE")
+    }
+}
+
+/// A location in the source code
+#[derive(Debug, Serialize)]
+pub struct Location {
+    /// Source file
+    pub file: String,
+
+    /// Line number, starting at 1
+    pub line: usize,
+
+    /// Column number, starting at 1
+    pub column: usize,
+}
+
+impl Location {
+    fn new(span: Span, file: &str) -> Self {
+        let start = span.start();
+
+        Location {
+            file: file.to_string(),
+            line: start.line,
+            column: start.column + 1,
+        }
+    }
+}
+
+/// The `#[app]` module
+#[derive(Debug, Serialize)]
+pub struct AppMeta {
+    /// Name of the module
+    pub name: String,
+
+    /// Location of the module name
+    pub location: Location,
+
+    /// The `device` argument
+    pub device: Option<String>,
+
+    /// The `peripherals` argument
+    pub peripherals: bool,
+
+    /// The `cores` argument
+    pub cores: u8,
+
+    /// The `dispatchers` argument
+    pub dispatchers: Vec<String>,
+
+    /// The `#[init]` function
+    pub init: TaskMeta,
+
+    /// The `#[idle]` function
+    pub idle: Option<TaskMeta>,
+
+    /// Hardware tasks
+    pub hardware_tasks: Vec<TaskMeta>,
+
+    /// Software tasks
+    pub software_tasks: Vec<TaskMeta>,
+
+    /// Resources defined in `#[shared]`
+    pub shared_resources: Vec<SharedResourceMeta>,
+
+    /// Resources defined in `#[local]`
+    pub local_resources: Vec<LocalResourceMeta>,
+
+    /// Monotonic clocks
+    pub monotonics: Vec<MonotonicMeta>,
+}
+
+impl AppMeta {
+    fn new(app: &App, file: &str) -> Self {
+        AppMeta {
+            name: app.name.to_string(),
+            location: Location::new(app.name.span(), file),
+            device: app.args.device.as_ref().map(tokens),
+            peripherals: app.args.peripherals,
+            cores: app.args.cores,
+            dispatchers: app
+                .args
+                .extern_interrupts
+                .keys()
+                .map(|name| name.to_string())
+                .collect(),
+            init: TaskMeta {
+                name: app.init.name.to_string(),
+                location: Location::new(app.init.name.span(), file),
+                kind: TaskKind::Init,
+                priority: 0,
+                threshold: None,
+                core: app.init.args.core,
+                binds: None,
+                capacity: None,
+                is_async: false,
+                is_extern: false,
+                inputs: vec![],
+                wcet_ns: None,
+                period_ns: None,
+                deadline_ns: None,
+                cfgs: vec![],
+                shared_resources: vec![],
+                local_resources: local_accesses(&app.init.args.local_resources, file),
+            },
+            idle: app.idle.as_ref().map(|idle| TaskMeta {
+                name: idle.name.to_string(),
+                location: Location::new(idle.name.span(), file),
+                kind: TaskKind::Idle,
+                priority: 0,
+                threshold: None,
+                core: idle.args.core,
+                binds: None,
+                capacity: None,
+                is_async: idle.is_async,
+                is_extern: false,
+                inputs: vec![],
+                wcet_ns: None,
+                period_ns: None,
+                deadline_ns: None,
+                cfgs: vec![],
+                shared_resources: shared_accesses(&idle.args.shared_resources, file),
+                local_resources: local_accesses(&idle.args.local_resources, file),
+            }),
+            hardware_tasks: app
+                .hardware_tasks
+                .iter()
+                .map(|(name, task)| TaskMeta {
+                    name: name.to_string(),
+                    location: Location::new(name.span(), file),
+                    kind: TaskKind::Hardware,
+                    priority: task.args.priority,
+                    threshold: task.args.threshold,
+                    core: task.args.core,
+                    binds: Some(task.args.binds.to_string()),
+                    capacity: None,
+                    is_async: false,
+                    is_extern: task.is_extern,
+                    inputs: vec![],
+                    wcet_ns: task.args.timing.wcet.map(nanos),
+                    period_ns: task.args.timing.period.map(nanos),
+                    deadline_ns: task.args.timing.deadline.map(nanos),
+                    cfgs: task.cfgs.iter().map(tokens).collect(),
+                    shared_resources: shared_accesses(&task.args.shared_resources, file),
+                    local_resources: local_accesses(&task.args.local_resources, file),
+                })
+                .collect(),
+            software_tasks: app
+                .software_tasks
+                .iter()
+                .map(|(name, task)| TaskMeta {
+                    name: name.to_string(),
+                    location: Location::new(name.span(), file),
+                    kind: TaskKind::Software,
+                    priority: task.args.priority,
+                    threshold: task.args.threshold,
+                    core: task.args.core,
+                    binds: None,
+                    capacity: Some(task.args.capacity),
+                    is_async: task.is_async,
+                    is_extern: task.is_extern,
+                    inputs: task.inputs.iter().map(|input| tokens(&input.ty)).collect(),
+                    wcet_ns: task.args.timing.wcet.map(nanos),
+                    period_ns: task.args.timing.period.map(nanos),
+                    deadline_ns: task.args.timing.deadline.map(nanos),
+                    cfgs: task.cfgs.iter().map(tokens).collect(),
+                    shared_resources: shared_accesses(&task.args.shared_resources, file),
+                    local_resources: local_accesses(&task.args.local_resources, file),
+                })
+                .collect(),
+            shared_resources: app
+                .shared_resources
+                .iter()
+                .map(|(name, res)| SharedResourceMeta {
+                    name: name.to_string(),
+                    location: Location::new(name.span(), file),
+                    ty: tokens(&res.ty),
+                    cfgs: res.cfgs.iter().map(tokens).collect(),
+                    lock_free: res.properties.lock_free,
+                    atomic: res.properties.atomic,
+                })
+                .collect(),
+            local_resources: app
+                .local_resources
+                .iter()
+                .map(|(name, res)| LocalResourceMeta {
+                    name: name.to_string(),
+                    location: Location::new(name.span(), file),
+                    ty: tokens(&res.ty),
+                    cfgs: res.cfgs.iter().map(tokens).collect(),
+                })
+                .collect(),
+            monotonics: app
+                .monotonics
+                .iter()
+                .map(|(name, monotonic)| MonotonicMeta {
+                    name: name.to_string(),
+                    location: Location::new(name.span(), file),
+                    ty: tokens(&monotonic.ty),
+                    binds: monotonic.args.binds.to_string(),
+                    priority: monotonic.args.priority,
+                    core: monotonic.args.core,
+                    default: monotonic.args.default,
+                    cfgs: monotonic.cfgs.iter().map(tokens).collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The kind of a task
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TaskKind {
+    /// The `#[init]` function
+    Init,
+
+    /// The `#[idle]` function
+    Idle,
+
+    /// A hardware task: `#[task(binds = ..)]`
+    Hardware,
+
+    /// A software task: `#[task]`
+    Software,
+}
+
+/// A task, or the `#[init]` / `#[idle]` functions
+#[derive(Debug, Serialize)]
+pub struct TaskMeta {
+    /// Name of the task
+    pub name: String,
+
+    /// Location of the task name
+    pub location: Location,
+
+    /// The kind of task
+    pub kind: TaskKind,
+
+    /// Priority of the task; 0 for `#[init]` and `#[idle]`
+    pub priority: u8,
+
+    /// Preemption threshold of the task, if given
+    pub threshold: Option<u8>,
+
+    /// The core the task runs on
+    pub core: u8,
+
+    /// The interrupt or exception a hardware task is bound to
+    pub binds: Option<String>,
+
+    /// Capacity of a software task
+    pub capacity: Option<u8>,
+
+    /// The task is `async`
+    pub is_async: bool,
+
+    /// The task is declared in an `extern` block
+    pub is_extern: bool,
+
+    /// Types of the messages of a software task
+    pub inputs: Vec<String>,
+
+    /// Worst-case execution time, in nanoseconds
+    pub wcet_ns: Option<u64>,
+
+    /// Period, in nanoseconds
+    pub period_ns: Option<u64>,
+
+    /// Deadline, in nanoseconds
+    pub deadline_ns: Option<u64>,
+
+    /// `#[cfg]` attributes
+    pub cfgs: Vec<String>,
+
+    /// Shared resources this task has access to
+    pub shared_resources: Vec<SharedAccessMeta>,
+
+    /// Local resources this task has access to
+    pub local_resources: Vec<LocalAccessMeta>,
+}
+
+/// An entry of `shared = [..]`
+#[derive(Debug, Serialize)]
+pub struct SharedAccessMeta {
+    /// Name of the resource
+    pub name: String,
+
+    /// Location of the entry
+    pub location: Location,
+
+    /// The kind of access
+    pub access: AccessKind,
+
+    /// `#[cfg]` attributes of the entry
+    pub cfgs: Vec<String>,
+}
+
+/// Resource access
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AccessKind {
+    /// `x`
+    Exclusive,
+
+    /// `&x`
+    Shared,
+}
+
+impl From<Access> for AccessKind {
+    fn from(access: Access) -> Self {
+        match access {
+            Access::Exclusive => AccessKind::Exclusive,
+            Access::Shared => AccessKind::Shared,
+        }
+    }
+}
+
+fn shared_accesses(resources: &SharedResources, file: &str) -> Vec<SharedAccessMeta> {
+    resources
+        .iter()
+        .map(|(name, access)| SharedAccessMeta {
+            name: name.to_string(),
+            location: Location::new(name.span(), file),
+            access: access.access.into(),
+            cfgs: access.cfgs.iter().map(tokens).collect(),
+        })
+        .collect()
+}
+
+/// An entry of `local = [..]`
+#[derive(Debug, Serialize)]
+pub struct LocalAccessMeta {
+    /// Name of the resource
+    pub name: String,
+
+    /// Location of the entry
+    pub location: Location,
+
+    /// Type of a resource declared in place (`local = [NAME: TYPE = EXPR]`)
+    pub ty: Option<String>,
+
+    /// Initial value of a resource declared in place (`local = [NAME: TYPE = EXPR]`)
+    pub expr: Option<String>,
+
+    /// `#[cfg]` attributes of the entry
+    pub cfgs: Vec<String>,
+}
+
+fn local_accesses(resources: &LocalResources, file: &str) -> Vec<LocalAccessMeta> {
+    resources
+        .iter()
+        .map(|(name, task_local)| {
+            let (ty, expr) = match task_local {
+                TaskLocal::External { .. } => (None, None),
+                TaskLocal::Declared(local) => (Some(tokens(&local.ty)), Some(tokens(&local.expr))),
+            };
+
+            LocalAccessMeta {
+                name: name.to_string(),
+                location: Location::new(name.span(), file),
+                ty,
+                expr,
+                cfgs: task_local.cfgs().iter().map(tokens).collect(),
+            }
+        })
+        .collect()
+}
+
+/// A resource defined in `#[shared]`
+#[derive(Debug, Serialize)]
+pub struct SharedResourceMeta {
+    /// Name of the resource
+    pub name: String,
+
+    /// Location of the field
+    pub location: Location,
+
+    /// Type of the resource
+    pub ty: String,
+
+    /// `#[cfg]` attributes
+    pub cfgs: Vec<String>,
+
+    /// The resource is `#[lock_free]`
+    pub lock_free: bool,
+
+    /// The resource is `#[atomic]`
+    pub atomic: bool,
+}
+
+/// A resource defined in `#[local]`
+#[derive(Debug, Serialize)]
+pub struct LocalResourceMeta {
+    /// Name of the resource
+    pub name: String,
+
+    /// Location of the field
+    pub location: Location,
+
+    /// Type of the resource
+    pub ty: String,
+
+    /// `#[cfg]` attributes
+    pub cfgs: Vec<String>,
+}
+
+/// A `#[monotonic]`
+#[derive(Debug, Serialize)]
+pub struct MonotonicMeta {
+    /// Name of the monotonic
+    pub name: String,
+
+    /// Location of the name
+    pub location: Location,
+
+    /// Type of the monotonic
+    pub ty: String,
+
+    /// The interrupt or exception the monotonic is bound to
+    pub binds: String,
+
+    /// Priority of the monotonic
+    pub priority: Option<u8>,
+
+    /// The core the monotonic runs on
+    pub core: u8,
+
+    /// This is the default monotonic
+    pub default: bool,
+
+    /// `#[cfg]` attributes
+    pub cfgs: Vec<String>,
+}
+
+/// The results of the analysis
+#[derive(Debug, Serialize)]
+pub struct AnalysisMeta {
+    /// Priority levels that need an async executor
+    pub async_priorities: Vec<ExecutorMeta>,
+
+    /// Message channels
+    pub channels: Vec<ChannelMeta>,
+
+    /// Dispatcher interrupts
+    pub dispatchers: Vec<DispatcherMeta>,
+
+    /// Every bound interrupt and exception
+    pub vector_table: Vec<VectorMeta>,
+
+    /// Shared resources that are used
+    pub shared_resources: Vec<String>,
+
+    /// Local resources that are used
+    pub local_resources: Vec<String>,
+
+    /// The core(s) that access each shared resource
+    pub locations: Vec<ResourceLocationMeta>,
+
+    /// Ownership of the shared resources
+    pub ownerships: Vec<OwnershipMeta>,
+
+    /// `#[atomic]` shared resources that are used; they are never locked so they have no
+    /// ownership
+    pub atomic_resources: Vec<String>,
+
+    /// Ownership of the shared resources in each configuration, see
+    /// [`Analysis::gated_ownerships`]
+    pub gated_ownerships: Vec<GatedOwnershipMeta>,
+
+    /// Types that must implement `Send`
+    pub send_types: Vec<SendTypeMeta>,
+
+    /// Types that must implement `Sync`
+    pub sync_types: Vec<String>,
+
+    /// Worst-case response times
+    pub response_times: Vec<ResponseTimeMeta>,
+}
+
+impl AnalysisMeta {
+    fn new(analysis: &Analysis) -> Self {
+        AnalysisMeta {
+            async_priorities: analysis
+                .async_priorities
+                .iter()
+                .flat_map(|(core, priorities)| {
+                    priorities.iter().map(move |priority| ExecutorMeta {
+                        core: *core,
+                        priority: *priority,
+                    })
+                })
+                .collect(),
+            channels: analysis
+                .channels
+                .iter()
+                .flat_map(|(core, channels)| {
+                    channels.iter().map(move |(priority, channel)| ChannelMeta {
+                        core: *core,
+                        priority: *priority,
+                        capacity: channel.capacity,
+                        capacities: channel
+                            .capacities
+                            .iter()
+                            .map(|capacity| CapacityMeta {
+                                capacity: capacity.value,
+                                cfg: cfg(&capacity.cfg),
+                            })
+                            .collect(),
+                        tasks: channel.tasks.iter().map(|task| task.to_string()).collect(),
+                    })
+                })
+                .collect(),
+            dispatchers: analysis
+                .dispatchers
+                .iter()
+                .flat_map(|(core, dispatchers)| {
+                    dispatchers
+                        .iter()
+                        .map(move |(priority, interrupt)| DispatcherMeta {
+                            core: *core,
+                            priority: *priority,
+                            interrupt: interrupt.to_string(),
+                        })
+                })
+                .collect(),
+            vector_table: analysis
+                .vector_table
+                .iter()
+                .flat_map(|(core, vectors)| {
+                    vectors.iter().flat_map(move |(name, vector)| {
+                        vector.handlers.iter().map(move |handler| VectorMeta {
+                            core: *core,
+                            vector: name.to_string(),
+                            binding: vector.binding.into(),
+                            handler: (&handler.value).into(),
+                            cfg: cfg(&handler.cfg),
+                        })
+                    })
+                })
+                .collect(),
+            shared_resources: analysis
+                .shared_resources
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            local_resources: analysis
+                .local_resources
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            locations: analysis
+                .locations
+                .iter()
+                .map(|(name, location)| ResourceLocationMeta {
+                    resource: name.to_string(),
+                    cores: match location {
+                        analyze::Location::Owned { core } => vec![*core],
+                        analyze::Location::Shared { cores } => cores.iter().cloned().collect(),
+                    },
+                })
+                .collect(),
+            ownerships: analysis
+                .ownerships
+                .iter()
+                .map(|(name, ownership)| OwnershipMeta {
+                    resource: name.to_string(),
+                    ownership: (*ownership).into(),
+                })
+                .collect(),
+            atomic_resources: analysis
+                .atomic_resources
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            gated_ownerships: analysis
+                .gated_ownerships
+                .iter()
+                .flat_map(|(name, gated)| {
+                    gated.iter().map(move |gated| GatedOwnershipMeta {
+                        resource: name.to_string(),
+                        cfg: cfg(&gated.cfg),
+                        ownership: gated.value.into(),
+                    })
+                })
+                .collect(),
+            send_types: analysis
+                .send_types
+                .iter()
+                .map(|(ty, send_cfg)| SendTypeMeta {
+                    ty: tokens(ty),
+                    cfg: cfg(send_cfg),
+                })
+                .collect(),
+            sync_types: analysis.sync_types.iter().map(tokens).collect(),
+            response_times: analysis
+                .response_times
+                .iter()
+                .map(|(name, response_time)| ResponseTimeMeta {
+                    task: name.to_string(),
+                    wcet_ns: nanos(response_time.wcet),
+                    deadline_ns: nanos(response_time.deadline),
+                    blocking_ns: nanos(response_time.blocking),
+                    response_time_ns: response_time.response_time.map(nanos),
+                    missing_timing: response_time
+                        .missing_timing
+                        .iter()
+                        .map(|task| task.to_string())
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A priority level that needs an async executor
+#[derive(Debug, Serialize)]
+pub struct ExecutorMeta {
+    /// The core of the executor
+    pub core: u8,
+
+    /// Priority of the executor
+    pub priority: u8,
+}
+
+/// A message channel
+#[derive(Debug, Serialize)]
+pub struct ChannelMeta {
+    /// The core of the channel
+    pub core: u8,
+
+    /// Dispatch priority
+    pub priority: u8,
+
+    /// Capacity of the channel
+    pub capacity: u8,
+
+    /// The terms of `capacity`, one per task, see [`analyze::Channel::capacities`]
+    pub capacities: Vec<CapacityMeta>,
+
+    /// Tasks that can be spawned on this channel
+    pub tasks: Vec<String>,
+}
+
+/// A term of the capacity of a channel
+#[derive(Debug, Serialize)]
+pub struct CapacityMeta {
+    /// The capacity this term adds
+    pub capacity: u8,
+
+    /// The condition under which the term is added, e.g. `feature = "a"`
+    pub cfg: Option<String>,
+}
+
+/// The dispatcher of a priority level
+#[derive(Debug, Serialize)]
+pub struct DispatcherMeta {
+    /// The core of the dispatcher
+    pub core: u8,
+
+    /// Dispatch priority
+    pub priority: u8,
+
+    /// The dispatcher interrupt
+    pub interrupt: String,
+}
+
+/// An entry of the vector table
+#[derive(Debug, Serialize)]
+pub struct VectorMeta {
+    /// The core of the vector
+    pub core: u8,
+
+    /// The interrupt or exception
+    pub vector: String,
+
+    /// Whether the vector is an interrupt or an exception
+    pub binding: BindingKind,
+
+    /// What handles the vector
+    pub handler: HandlerKind,
+
+    /// The condition under which `handler` handles the vector, e.g. `feature = "a"`; a vector
+    /// appears once per handler when several handlers under mutually exclusive `#[cfg]`s bind it
+    pub cfg: Option<String>,
+}
+
+/// The kind of vector, see [`Binding`]
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BindingKind {
+    /// A device specific interrupt
+    Interrupt,
+
+    /// A core exception
+    Exception,
+
+    /// A core exception with a fixed priority
+    FixedPriorityException,
+}
+
+impl From<Binding> for BindingKind {
+    fn from(binding: Binding) -> Self {
+        match binding {
+            Binding::Interrupt => BindingKind::Interrupt,
+            Binding::Exception => BindingKind::Exception,
+            Binding::FixedPriorityException => BindingKind::FixedPriorityException,
+        }
+    }
+}
+
+/// What handles a vector, see [`analyze::Handler`]
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum HandlerKind {
+    /// A hardware task
+    HardwareTask {
+        /// Name of the task
+        task: String,
+    },
+
+    /// The dispatcher of a priority level
+    Dispatcher {
+        /// Dispatch priority
+        priority: u8,
+    },
+
+    /// A monotonic timer
+    Monotonic {
+        /// Name of the monotonic
+        monotonic: String,
+    },
+}
+
+impl From<&analyze::Handler> for HandlerKind {
+    fn from(handler: &analyze::Handler) -> Self {
+        match handler {
+            analyze::Handler::HardwareTask(task) => HandlerKind::HardwareTask {
+                task: task.to_string(),
+            },
+            analyze::Handler::Dispatcher(priority) => HandlerKind::Dispatcher {
+                priority: *priority,
+            },
+            analyze::Handler::Monotonic(monotonic) => HandlerKind::Monotonic {
+                monotonic: monotonic.to_string(),
+            },
+        }
+    }
+}
+
+/// The core(s) that access a shared resource
+#[derive(Debug, Serialize)]
+pub struct ResourceLocationMeta {
+    /// Name of the resource
+    pub resource: String,
+
+    /// The cores, more than one if the resource is shared between cores
+    pub cores: Vec<u8>,
+}
+
+/// Ownership of a shared resource
+#[derive(Debug, Serialize)]
+pub struct OwnershipMeta {
+    /// Name of the resource
+    pub resource: String,
+
+    /// The ownership
+    #[serde(flatten)]
+    pub ownership: OwnershipKind,
+}
+
+/// Ownership of a shared resource in some configurations
+#[derive(Debug, Serialize)]
+pub struct GatedOwnershipMeta {
+    /// Name of the resource
+    pub resource: String,
+
+    /// The condition under which the resource has this ownership, e.g. `feature = "a"`
+    pub cfg: Option<String>,
+
+    /// The ownership
+    #[serde(flatten)]
+    pub ownership: OwnershipKind,
+}
+
+/// A type that must implement `Send`
+#[derive(Debug, Serialize)]
+pub struct SendTypeMeta {
+    /// The type
+    #[serde(rename = "type")]
+    pub ty: String,
+
+    /// The condition under which the type must be `Send`, e.g. `feature = "a"`
+    pub cfg: Option<String>,
+}
+
+/// The response-time analysis of a task, see [`crate::schedule::ResponseTime`]
+#[derive(Debug, Serialize)]
+pub struct ResponseTimeMeta {
+    /// Name of the task
+    pub task: String,
+
+    /// Worst-case execution time, in nanoseconds
+    pub wcet_ns: u64,
+
+    /// Deadline, in nanoseconds
+    pub deadline_ns: u64,
+
+    /// Longest blocking time, in nanoseconds
+    pub blocking_ns: u64,
+
+    /// Worst-case response time, in nanoseconds; `null` if it exceeds the deadline or can't be
+    /// computed
+    pub response_time_ns: Option<u64>,
+
+    /// Tasks that lack the timing annotations needed to compute the response time
+    pub missing_timing: Vec<String>,
+}
+
+/// Resource ownership, see [`analyze::Ownership`]
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum OwnershipKind {
+    /// Owned by a single task
+    Owned {
+        /// Priority of the task that owns this resource
+        priority: u8,
+    },
+
+    /// Co-owned by tasks of the same priority
+    CoOwned {
+        /// Priority of the tasks that co-own this resource
+        priority: u8,
+    },
+
+    /// Contended by tasks of different priorities
+    Contended {
+        /// Priority ceiling
+        ceiling: u8,
+
+        /// Ceiling of the shared (`&x`) accesses
+        read_ceiling: u8,
+    },
+
+    /// Accessed by tasks of different priorities, only through shared references (`&x`)
+    ReadOnly {
+        /// Highest priority of the tasks that access this resource
+        ceiling: u8,
+    },
+}
+
+impl From<analyze::Ownership> for OwnershipKind {
+    fn from(ownership: analyze::Ownership) -> Self {
+        match ownership {
+            analyze::Ownership::Owned { priority } => OwnershipKind::Owned { priority },
+            analyze::Ownership::CoOwned { priority } => OwnershipKind::CoOwned { priority },
+            analyze::Ownership::Contended {
+                ceiling,
+                read_ceiling,
+            } => OwnershipKind::Contended {
+                ceiling,
+                read_ceiling,
+            },
+            analyze::Ownership::ReadOnly { ceiling } => OwnershipKind::ReadOnly { ceiling },
+        }
+    }
+}
+
+fn nanos(duration: Duration) -> u64 {
+    duration.as_nanos() as u64
+}
+
+fn tokens(tokens: &impl ToTokens) -> String {
+    tokens.to_token_stream().to_string()
+}
+
+fn cfg(cfg: &Cfg) -> Option<String> {
+    if cfg.is_always() {
+        None
+    } else {
+        Some(cfg.to_string())
+    }
+}
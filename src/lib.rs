@@ -25,9 +25,19 @@ use crate::ast::App;
 mod accessors;
 pub mod analyze;
 pub mod ast;
+mod body;
+pub mod cfg;
 mod check;
+pub mod device;
+mod dot;
+#[cfg(feature = "serde")]
+pub mod export;
+pub mod lint;
 mod optimize;
 mod parse;
+mod print;
+pub mod schedule;
+pub mod sim;
 #[cfg(test)]
 mod tests;
 
@@ -99,31 +109,35 @@ impl<'a> Context<'a> {
         self.is_init() || self.is_idle()
     }
 
-    /// Whether this context has shared resources
+    /// Whether this context, or any variant of the task, has shared resources
     pub fn has_shared_resources(&self, app: &App) -> bool {
         match *self {
-            Context::HardwareTask(name) => {
-                !app.hardware_tasks[name].args.shared_resources.is_empty()
-            }
+            Context::HardwareTask(name) => app
+                .hardware_tasks
+                .variants(name)
+                .any(|task| !task.args.shared_resources.is_empty()),
             Context::Idle => !app.idle.as_ref().unwrap().args.shared_resources.is_empty(),
             Context::Init => false,
-            Context::SoftwareTask(name) => {
-                !app.software_tasks[name].args.shared_resources.is_empty()
-            }
+            Context::SoftwareTask(name) => app
+                .software_tasks
+                .variants(name)
+                .any(|task| !task.args.shared_resources.is_empty()),
         }
     }
 
-    /// Whether this context has local resources
+    /// Whether this context, or any variant of the task, has local resources
     pub fn has_local_resources(&self, app: &App) -> bool {
         match *self {
-            Context::HardwareTask(name) => {
-                !app.hardware_tasks[name].args.local_resources.is_empty()
-            }
+            Context::HardwareTask(name) => app
+                .hardware_tasks
+                .variants(name)
+                .any(|task| !task.args.local_resources.is_empty()),
             Context::Idle => !app.idle.as_ref().unwrap().args.local_resources.is_empty(),
             Context::Init => !app.init.args.local_resources.is_empty(),
-            Context::SoftwareTask(name) => {
-                !app.software_tasks[name].args.local_resources.is_empty()
-            }
+            Context::SoftwareTask(name) => app
+                .software_tasks
+                .variants(name)
+                .any(|task| !task.args.local_resources.is_empty()),
         }
     }
 }
@@ -138,6 +152,21 @@ pub struct Settings {
     pub parse_extern_interrupt: bool,
     /// Whether to "compress" priorities or not
     pub optimize_priorities: bool,
+    /// Whether to accept `async` software tasks (and an `async` `#[idle]`) or not
+    pub parse_async_tasks: bool,
+    /// Whether to only require `Send` from the inputs of software tasks that may be spawned from
+    /// another priority or core, according to the `spawn` calls found in the `#[app]` module;
+    /// unsound if tasks are spawned from outside the module
+    pub precise_send_types: bool,
+    /// The level (allow, warn or deny) of each lint
+    pub lints: lint::Lints,
+    /// The interrupts and exceptions of the target device; if given, `binds` and `dispatchers`
+    /// are checked against it
+    pub device: Option<device::Device>,
+    /// The active cfg options; if given, the tasks, resources, monotonics and declared locals
+    /// whose `#[cfg]` doesn't hold are removed before the application is checked and analyzed.
+    /// Meant for host tools, the proc macro leaves `#[cfg]` to the compiler
+    pub cfg: Option<cfg::CfgSet>,
 }
 
 /// Parses the input of the `#[app]` attribute
@@ -156,13 +185,29 @@ pub fn parse2(
     settings: Settings,
 ) -> Result<(P<ast::App>, P<analyze::Analysis>), syn::parse::Error> {
     let mut app = parse::app(args, input, &settings)?;
-    check::app(&app)?;
+    if let Some(set) = &settings.cfg {
+        cfg::prune(&mut app, set);
+    }
+    check::app(&app, &settings)?;
     optimize::app(&mut app, &settings);
 
-    match analyze::app(&app) {
-        Err(e) => Err(e),
-        // If no errors, return the app and analysis results
-        Ok(analysis) => Ok((P::new(app), P::new(analysis))),
+    let mut analysis = analyze::app(&app, &settings)?;
+    analysis.response_times = schedule::app(&app, &analysis);
+    analysis.warnings = lint::app(&app, &analysis, &settings.lints)?;
+
+    // If no errors, return the app and analysis results
+    Ok((P::new(app), P::new(analysis)))
+}
+
+/// Combines all the errors into a single one; `Ok` if there are none
+fn combine_errors(errors: Vec<syn::Error>) -> Result<(), syn::Error> {
+    let mut errors = errors.into_iter();
+
+    if let Some(mut error) = errors.next() {
+        errors.for_each(|e| error.combine(e));
+        Err(error)
+    } else {
+        Ok(())
     }
 }
 
@@ -0,0 +1,566 @@
+//! Lints: checks of code that is legal but likely a mistake
+//!
+//! Each lint has a `Level`, set through `Settings::lints`. The lints at the `Warn` level are
+//! returned in `Analysis::warnings`, the ones at the `Deny` level make `parse` fail
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::{parse, Attribute, Ident, Stmt};
+
+use crate::{
+    analyze::Analysis,
+    ast::{App, LocalResources, SharedResources, TaskLocal},
+    cfg::Cfg,
+    schedule, Set,
+};
+
+/// A lint
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Lint {
+    /// A shared resource that no context accesses
+    UnusedSharedResource,
+
+    /// A shared resource that only one context accesses; it could be a local resource
+    SingleTaskSharedResource,
+
+    /// A `dispatchers` interrupt that doesn't dispatch any software task
+    UnusedDispatcher,
+
+    /// A task whose worst-case response time exceeds its deadline
+    UnschedulableTask,
+
+    /// A task with timing annotations whose response time can't be computed because a task that
+    /// can block or preempt it has no `wcet` / `period`
+    IncompleteTiming,
+
+    /// A resource listed in the `shared` / `local` argument of a context whose body never uses it
+    UnusedResourceAccess,
+
+    /// A resource used in the body of a context that doesn't list it in its `shared` / `local`
+    /// argument
+    UndeclaredResourceAccess,
+
+    /// A software task that is not spawned anywhere in the `#[app]` module
+    ///
+    /// Only the `#[app]` module is walked, so tasks spawned from other modules, callbacks or macro
+    /// expansions are reported too; this lint is allowed by default
+    NeverSpawned,
+
+    /// A context that lists a resource whose `#[cfg]` doesn't hold in all the configurations
+    /// where the context exists
+    InconsistentCfg,
+}
+
+impl Lint {
+    /// The name of the lint, e.g. `unused_shared_resource`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lint::UnusedSharedResource => "unused_shared_resource",
+            Lint::SingleTaskSharedResource => "single_task_shared_resource",
+            Lint::UnusedDispatcher => "unused_dispatcher",
+            Lint::UnschedulableTask => "unschedulable_task",
+            Lint::IncompleteTiming => "incomplete_timing",
+            Lint::UnusedResourceAccess => "unused_resource_access",
+            Lint::UndeclaredResourceAccess => "undeclared_resource_access",
+            Lint::NeverSpawned => "never_spawned",
+            Lint::InconsistentCfg => "inconsistent_cfg",
+        }
+    }
+}
+
+/// What to do when a lint fires
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Level {
+    /// Ignore it
+    Allow,
+
+    /// Report it in `Analysis::warnings`
+    Warn,
+
+    /// Report it as an error
+    Deny,
+}
+
+/// The level of each lint, all lints `Warn` by default
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct Lints {
+    /// Level of `Lint::UnusedSharedResource`
+    pub unused_shared_resource: Level,
+
+    /// Level of `Lint::SingleTaskSharedResource`
+    pub single_task_shared_resource: Level,
+
+    /// Level of `Lint::UnusedDispatcher`
+    pub unused_dispatcher: Level,
+
+    /// Level of `Lint::UnschedulableTask`
+    pub unschedulable_task: Level,
+
+    /// Level of `Lint::IncompleteTiming`
+    pub incomplete_timing: Level,
+
+    /// Level of `Lint::UnusedResourceAccess`
+    pub unused_resource_access: Level,
+
+    /// Level of `Lint::UndeclaredResourceAccess`
+    pub undeclared_resource_access: Level,
+
+    /// Level of `Lint::NeverSpawned`
+    pub never_spawned: Level,
+
+    /// Level of `Lint::InconsistentCfg`
+    pub inconsistent_cfg: Level,
+}
+
+impl Default for Lints {
+    fn default() -> Self {
+        Self {
+            unused_shared_resource: Level::Warn,
+            single_task_shared_resource: Level::Warn,
+            unused_dispatcher: Level::Warn,
+            unschedulable_task: Level::Warn,
+            incomplete_timing: Level::Warn,
+            unused_resource_access: Level::Warn,
+            undeclared_resource_access: Level::Warn,
+            never_spawned: Level::Allow,
+            inconsistent_cfg: Level::Warn,
+        }
+    }
+}
+
+impl Lints {
+    /// The level of `lint`
+    pub fn level(&self, lint: Lint) -> Level {
+        match lint {
+            Lint::UnusedSharedResource => self.unused_shared_resource,
+            Lint::SingleTaskSharedResource => self.single_task_shared_resource,
+            Lint::UnusedDispatcher => self.unused_dispatcher,
+            Lint::UnschedulableTask => self.unschedulable_task,
+            Lint::IncompleteTiming => self.incomplete_timing,
+            Lint::UnusedResourceAccess => self.unused_resource_access,
+            Lint::UndeclaredResourceAccess => self.undeclared_resource_access,
+            Lint::NeverSpawned => self.never_spawned,
+            Lint::InconsistentCfg => self.inconsistent_cfg,
+        }
+    }
+}
+
+/// A lint that fired
+#[derive(Clone, Debug)]
+pub struct Warning {
+    /// The lint
+    pub lint: Lint,
+
+    /// Where the offending code is
+    pub span: Span,
+
+    /// What is wrong
+    pub message: String,
+
+    /// Other code involved, with what it is, e.g. the declaration of a resource
+    pub notes: Vec<(Span, String)>,
+}
+
+/// Renders the warning as an item that makes the compiler emit a deprecation warning on stable,
+/// at the span of the warning
+///
+/// ``` text
+/// warning: use of deprecated constant `_::unused_dispatcher`: this dispatcher is never used
+/// ```
+impl ToTokens for Warning {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let name = format_ident!("{}", self.lint.name());
+        let message = &self.message;
+        // The compiler reports the use of the constant, so that's what carries the span
+        let use_name = Ident::new(self.lint.name(), self.span);
+        let use_ = quote_spanned!(self.span=> let _ = #use_name;);
+
+        // Each note is reported by its own constant
+        let note_name = format_ident!("{}_note", self.lint.name());
+        let notes = self.notes.iter().map(|(span, note)| {
+            let use_name = Ident::new(&note_name.to_string(), *span);
+            let use_ = quote_spanned!(*span=> let _ = #use_name;);
+
+            quote!(
+                const _: () = {
+                    #[deprecated(note = #note)]
+                    #[allow(non_upper_case_globals)]
+                    const #note_name: () = ();
+
+                    #use_
+                };
+            )
+        });
+
+        tokens.extend(quote!(
+            #[allow(clippy::let_unit_value)]
+            const _: () = {
+                #[deprecated(note = #message)]
+                #[allow(non_upper_case_globals)]
+                const #name: () = ();
+
+                #use_
+
+                #(#notes)*
+            };
+        ));
+    }
+}
+
+pub(crate) fn app(
+    app: &App,
+    analysis: &Analysis,
+    lints: &Lints,
+) -> Result<Vec<Warning>, syn::Error> {
+    let mut warnings = vec![];
+
+    for name in app.shared_resources.keys() {
+        if !analysis.shared_resources.contains(name) {
+            warnings.push(Warning {
+                lint: Lint::UnusedSharedResource,
+                span: name.span(),
+                message: format!("shared resource `{}` is never used", name),
+                notes: vec![],
+            });
+
+            continue;
+        }
+
+        // The variants of a task count as one context
+        let users = contexts(app)
+            .filter(|(_, resources)| resources.contains_key(name))
+            .map(|(context, _)| context)
+            .collect::<Set<_>>();
+
+        if let Some(user) = users.first().filter(|_| users.len() == 1) {
+            warnings.push(Warning {
+                lint: Lint::SingleTaskSharedResource,
+                span: name.span(),
+                message: format!(
+                    "shared resource `{}` is only used by `{}`, consider making it a local resource",
+                    name, user
+                ),
+                notes: vec![],
+            });
+        }
+    }
+
+    for name in app.args.extern_interrupts.keys() {
+        if !analysis
+            .dispatchers
+            .values()
+            .flat_map(|dispatchers| dispatchers.values())
+            .any(|used| used == name)
+        {
+            warnings.push(Warning {
+                lint: Lint::UnusedDispatcher,
+                span: name.span(),
+                message: format!("dispatcher `{}` is never used", name),
+                notes: vec![],
+            });
+        }
+    }
+
+    for (name, response_time) in &analysis.response_times {
+        if !response_time.missing_timing.is_empty() {
+            let tasks = response_time
+                .missing_timing
+                .iter()
+                .map(|task| format!("`{}`", task))
+                .collect::<Vec<_>>();
+
+            warnings.push(Warning {
+                lint: Lint::IncompleteTiming,
+                span: name.span(),
+                message: format!(
+                    "the response time of `{}` can't be computed, {} can block or preempt it but \
+                     lack(s) a `wcet` or `period`",
+                    name,
+                    tasks.join(", ")
+                ),
+                notes: vec![],
+            });
+        } else if !response_time.is_schedulable() {
+            warnings.push(Warning {
+                lint: Lint::UnschedulableTask,
+                span: name.span(),
+                message: format!(
+                    "the worst-case response time of `{}` exceeds its deadline of {} \
+                     (wcet: {}, blocking: {})",
+                    name,
+                    schedule::duration(response_time.deadline),
+                    schedule::duration(response_time.wcet),
+                    schedule::duration(response_time.blocking),
+                ),
+                notes: vec![],
+            });
+        }
+    }
+
+    for (context, body) in &analysis.bodies {
+        let (shared, local, stub) = declarations(app, context);
+
+        // An empty body is a stub, it's expected not to use its resources yet
+        if !body.opaque && !stub {
+            let unused = shared
+                .iter()
+                .filter(|name| !body.shared_resources.contains(**name))
+                .map(|name| (name, "shared"))
+                .chain(
+                    local
+                        .iter()
+                        .filter(|name| !body.local_resources.contains(**name))
+                        .map(|name| (name, "local")),
+                );
+
+            for (name, kind) in unused {
+                warnings.push(Warning {
+                    lint: Lint::UnusedResourceAccess,
+                    span: name.span(),
+                    message: format!(
+                        "`{}` has access to the {} resource `{}` but never uses it",
+                        context, kind, name
+                    ),
+                    notes: vec![],
+                });
+            }
+        }
+
+        let undeclared = body
+            .shared_resources
+            .iter()
+            .filter(|name| !shared.contains(name))
+            .map(|name| (name, "shared"))
+            .chain(
+                body.local_resources
+                    .iter()
+                    .filter(|name| !local.contains(name))
+                    .map(|name| (name, "local")),
+            );
+
+        for (name, kind) in undeclared {
+            warnings.push(Warning {
+                lint: Lint::UndeclaredResourceAccess,
+                span: name.span(),
+                message: format!(
+                    "`{}` uses the {} resource `{}` but doesn't list it in its `{}` argument",
+                    context, kind, name, kind
+                ),
+                notes: vec![],
+            });
+        }
+    }
+
+    // `extern` tasks can spawn anything
+    let extern_tasks = app.hardware_tasks.values().any(|task| task.is_extern)
+        || app.software_tasks.values().any(|task| task.is_extern);
+    if !extern_tasks {
+        for name in app.software_tasks.keys() {
+            if !analysis.user_code_spawns.contains(name)
+                && !analysis
+                    .bodies
+                    .values()
+                    .any(|body| body.spawns.contains(name))
+            {
+                warnings.push(Warning {
+                    lint: Lint::NeverSpawned,
+                    span: name.span(),
+                    message: format!("software task `{}` is never spawned", name),
+                    notes: vec![],
+                });
+            }
+        }
+    }
+
+    // A context that can exist without a resource it lists doesn't compile in that configuration;
+    // the entry itself may be gated
+    for (context, cfgs, shared, local) in resource_lists(app) {
+        let context_cfg = Cfg::from_attrs(cfgs);
+        let listed = shared
+            .into_iter()
+            .flatten()
+            .filter_map(|(name, access)| {
+                app.shared_resources
+                    .get_key_value(name)
+                    .map(|(decl, res)| (name, &access.cfgs, decl, &res.cfgs, "shared"))
+            })
+            .chain(
+                local
+                    .iter()
+                    .filter_map(|(name, local)| match local {
+                        TaskLocal::External { cfgs } => Some((name, cfgs)),
+                        _ => None,
+                    })
+                    .filter_map(|(name, entry_cfgs)| {
+                        app.local_resources
+                            .get_key_value(name)
+                            .map(|(decl, res)| (name, entry_cfgs, decl, &res.cfgs, "local"))
+                    }),
+            );
+
+        for (name, entry_cfgs, decl, resource_cfgs, kind) in listed {
+            let entry_cfg = context_cfg.clone().and(Cfg::from_attrs(entry_cfgs));
+            let resource_cfg = Cfg::from_attrs(resource_cfgs);
+            if !entry_cfg.implies(&resource_cfg) {
+                warnings.push(Warning {
+                    lint: Lint::InconsistentCfg,
+                    span: name.span(),
+                    message: format!(
+                        "`{}` can exist without the {} resource `{}` it lists",
+                        context, kind, name
+                    ),
+                    notes: vec![(
+                        decl.span(),
+                        format!(
+                            "the {} resource `{}` only exists with `cfg({})`",
+                            kind, decl, resource_cfg
+                        ),
+                    )],
+                });
+            }
+        }
+    }
+
+    let mut errors = vec![];
+    warnings.retain(|warning| match lints.level(warning.lint) {
+        Level::Allow => false,
+        Level::Warn => true,
+        Level::Deny => {
+            errors.push(parse::Error::new(
+                warning.span,
+                format!(
+                    "{} (lint `{}` is set to deny)",
+                    warning.message,
+                    warning.lint.name()
+                ),
+            ));
+            for (span, note) in &warning.notes {
+                errors.push(parse::Error::new(*span, note));
+            }
+            false
+        }
+    });
+
+    crate::combine_errors(errors)?;
+
+    Ok(warnings)
+}
+
+/// The shared and local resources listed in the arguments of `context`, and whether its body is
+/// empty; the lists of the variants of a task are merged, like their bodies
+fn declarations<'a>(app: &'a App, context: &Ident) -> (Vec<&'a Ident>, Vec<&'a Ident>, bool) {
+    let variants: Vec<(Option<&SharedResources>, &LocalResources, &[Stmt])> =
+        if *context == app.init.name {
+            vec![(None, &app.init.args.local_resources, &app.init.stmts)]
+        } else if let Some(idle) = app.idle.as_ref().filter(|idle| idle.name == *context) {
+            vec![(
+                Some(&idle.args.shared_resources),
+                &idle.args.local_resources,
+                &idle.stmts,
+            )]
+        } else {
+            app.hardware_tasks
+                .variants(context)
+                .map(|task| {
+                    (
+                        Some(&task.args.shared_resources),
+                        &task.args.local_resources,
+                        &task.stmts[..],
+                    )
+                })
+                .chain(app.software_tasks.variants(context).map(|task| {
+                    (
+                        Some(&task.args.shared_resources),
+                        &task.args.local_resources,
+                        &task.stmts[..],
+                    )
+                }))
+                .collect()
+        };
+
+    let mut shared = Vec::<&Ident>::new();
+    let mut local = Vec::<&Ident>::new();
+    for (shared_resources, local_resources, _) in &variants {
+        for name in shared_resources.iter().flat_map(|shared| shared.keys()) {
+            if !shared.contains(&name) {
+                shared.push(name);
+            }
+        }
+        for name in local_resources.keys() {
+            if !local.contains(&name) {
+                local.push(name);
+            }
+        }
+    }
+
+    (
+        shared,
+        local,
+        variants.iter().all(|(_, _, stmts)| stmts.is_empty()),
+    )
+}
+
+/// All the contexts, along with their `#[cfg]`s and the resources they list
+fn resource_lists(
+    app: &App,
+) -> impl Iterator<
+    Item = (
+        &Ident,
+        &[Attribute],
+        Option<&SharedResources>,
+        &LocalResources,
+    ),
+> {
+    let no_cfgs: &[Attribute] = &[];
+
+    Some((
+        &app.init.name,
+        no_cfgs,
+        None,
+        &app.init.args.local_resources,
+    ))
+    .into_iter()
+    .chain(app.idle.iter().map(move |idle| {
+        (
+            &idle.name,
+            no_cfgs,
+            Some(&idle.args.shared_resources),
+            &idle.args.local_resources,
+        )
+    }))
+    .chain(app.hardware_tasks.iter().map(|(name, task)| {
+        (
+            name,
+            &task.cfgs[..],
+            Some(&task.args.shared_resources),
+            &task.args.local_resources,
+        )
+    }))
+    .chain(app.software_tasks.iter().map(|(name, task)| {
+        (
+            name,
+            &task.cfgs[..],
+            Some(&task.args.shared_resources),
+            &task.args.local_resources,
+        )
+    }))
+}
+
+/// The contexts that can access shared resources, along with the resources they access
+fn contexts(app: &App) -> impl Iterator<Item = (&Ident, &SharedResources)> {
+    app.idle
+        .iter()
+        .map(|idle| (&idle.name, &idle.args.shared_resources))
+        .chain(
+            app.hardware_tasks
+                .iter()
+                .map(|(name, task)| (name, &task.args.shared_resources)),
+        )
+        .chain(
+            app.software_tasks
+                .iter()
+                .map(|(name, task)| (name, &task.args.shared_resources)),
+        )
+}
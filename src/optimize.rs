@@ -1,4 +1,4 @@
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use crate::{ast::App, Settings};
 
@@ -6,31 +6,56 @@ pub fn app(app: &mut App, settings: &Settings) {
     // "compress" priorities
     // If the user specified, for example, task priorities of "1, 3, 6",
     // compress them into "1, 2, 3" as to leave no gaps
+    // Each core has its own priority levels, so they are compressed core by core
     if settings.optimize_priorities {
-        // all task priorities ordered in ascending order
-        let priorities = app
+        // all task priorities of each core ordered in ascending order
+        let mut priorities = BTreeMap::<_, BTreeSet<_>>::new();
+        for (core, priority) in app
             .hardware_tasks
             .values()
-            .map(|task| Some(task.args.priority))
+            .map(|task| (task.args.core, task.args.priority))
             .chain(
                 app.software_tasks
                     .values()
-                    .map(|task| Some(task.args.priority)),
+                    .map(|task| (task.args.core, task.args.priority)),
             )
-            .collect::<BTreeSet<_>>();
+        {
+            priorities.entry(core).or_default().insert(priority);
+        }
 
         let map = priorities
             .iter()
-            .cloned()
-            .zip(1..)
+            .flat_map(|(core, priorities)| {
+                priorities
+                    .iter()
+                    .zip(1..)
+                    .map(move |(priority, new)| ((*core, *priority), new))
+            })
             .collect::<HashMap<_, _>>();
 
+        // A threshold keeps out the tasks of a priority up to it, so it becomes the compressed
+        // priority of the highest task priority that's not above it
+        let threshold = |core, threshold| {
+            priorities[&core]
+                .iter()
+                .filter(|priority| **priority <= threshold)
+                .count() as u8
+        };
+
         for task in app.hardware_tasks.values_mut() {
-            task.args.priority = map[&Some(task.args.priority)];
+            task.args.threshold = task
+                .args
+                .threshold
+                .map(|old| threshold(task.args.core, old));
+            task.args.priority = map[&(task.args.core, task.args.priority)];
         }
 
         for task in app.software_tasks.values_mut() {
-            task.args.priority = map[&Some(task.args.priority)];
+            task.args.threshold = task
+                .args
+                .threshold
+                .map(|old| threshold(task.args.core, old));
+            task.args.priority = map[&(task.args.core, task.args.priority)];
         }
     }
 }
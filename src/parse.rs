@@ -1,4 +1,5 @@
 mod app;
+mod consts;
 mod hardware_task;
 mod idle;
 mod init;
@@ -7,28 +8,55 @@ mod resource;
 mod software_task;
 mod util;
 
-use proc_macro2::TokenStream as TokenStream2;
+use proc_macro2::{Span, TokenStream as TokenStream2};
 use syn::{
     braced, parenthesized,
     parse::{self, Parse, ParseStream, Parser},
     token::{self, Brace},
-    Ident, Item, LitBool, LitInt, Path, Token,
+    Ident, Item, LitBool, Path, Token,
 };
 
 use crate::{
     ast::{
-        App, AppArgs, HardwareTaskArgs, IdleArgs, InitArgs, MonotonicArgs, SoftwareTaskArgs,
-        TaskLocal,
+        App, AppArgs, Binding, ExternInterrupts, HardwareTaskArgs, IdleArgs, InitArgs,
+        MonotonicArgs, SoftwareTaskArgs, TaskLocal, Timing,
     },
-    Either, Settings,
+    device, Either, Settings,
 };
+use consts::Consts;
 
 // Parse the app, both app arguments and body (input)
 pub fn app(args: TokenStream2, input: TokenStream2, settings: &Settings) -> parse::Result<App> {
-    let args = AppArgs::parse(args)?;
-    let input: Input = syn::parse2(input)?;
+    let mut errors = vec![];
+
+    let args = AppArgs::parse(args).unwrap_or_else(|e| {
+        errors.push(e);
+
+        // Keep going with the default arguments to also report the errors in the module
+        AppArgs {
+            device: None,
+            peripherals: true,
+            cores: 1,
+            extern_interrupts: ExternInterrupts::new(),
+            dispatchers_span: None,
+        }
+    });
+
+    let input: Input = match syn::parse2(input) {
+        Ok(input) => input,
+        Err(e) => {
+            errors.push(e);
+            return Err(crate::combine_errors(errors).unwrap_err());
+        }
+    };
 
-    App::parse(args, input, settings)
+    match App::parse(args, input, settings) {
+        Ok(app) => crate::combine_errors(errors).map(|_| app),
+        Err(e) => {
+            errors.push(e);
+            Err(crate::combine_errors(errors).unwrap_err())
+        }
+    }
 }
 
 pub(crate) struct Input {
@@ -72,54 +100,58 @@ fn init_args(tokens: TokenStream2) -> parse::Result<InitArgs> {
             return Ok(InitArgs::default());
         }
 
+        let mut core = None;
         let mut local_resources = None;
 
         let content;
         parenthesized!(content in input);
 
-        if !content.is_empty() {
-            loop {
-                // Parse identifier name
-                let ident: Ident = content.parse()?;
-                // Handle equal sign
-                let _: Token![=] = content.parse()?;
-
-                match &*ident.to_string() {
-                    "local" => {
-                        if local_resources.is_some() {
-                            return Err(parse::Error::new(
-                                ident.span(),
-                                "argument appears more than once",
-                            ));
-                        }
-
-                        local_resources = Some(util::parse_local_resources(&content)?);
-                    }
-                    _ => {
-                        return Err(parse::Error::new(ident.span(), "unexpected argument"));
+        util::parse_arguments(&content, |ident, content| {
+            match &*ident.to_string() {
+                "core" => {
+                    if core.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
                     }
+
+                    core = Some(util::parse_core(content)?);
                 }
 
-                if content.is_empty() {
-                    break;
+                "local" => {
+                    if local_resources.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
+                    }
+
+                    local_resources = Some(util::parse_local_resources(content)?);
+                }
+                _ => {
+                    return Err(parse::Error::new(ident.span(), "unexpected argument"));
                 }
-                // Handle comma: ,
-                let _: Token![,] = content.parse()?;
             }
-        }
 
+            Ok(())
+        })?;
+
+        let mut errors = vec![];
         if let Some(locals) = &local_resources {
             for (ident, task_local) in locals {
-                if let TaskLocal::External = task_local {
-                    return Err(parse::Error::new(
+                if let TaskLocal::External { .. } = task_local {
+                    errors.push(parse::Error::new(
                         ident.span(),
                         "only declared local resources are allowed in init",
                     ));
                 }
             }
         }
+        crate::combine_errors(errors)?;
 
         Ok(InitArgs {
+            core: core.unwrap_or(0),
             local_resources: local_resources.unwrap_or_default(),
         })
     })
@@ -132,55 +164,58 @@ fn idle_args(tokens: TokenStream2) -> parse::Result<IdleArgs> {
             return Ok(IdleArgs::default());
         }
 
+        let mut core = None;
         let mut shared_resources = None;
         let mut local_resources = None;
 
         let content;
         parenthesized!(content in input);
-        if !content.is_empty() {
-            loop {
-                // Parse identifier name
-                let ident: Ident = content.parse()?;
-                // Handle equal sign
-                let _: Token![=] = content.parse()?;
-
-                match &*ident.to_string() {
-                    "shared" => {
-                        if shared_resources.is_some() {
-                            return Err(parse::Error::new(
-                                ident.span(),
-                                "argument appears more than once",
-                            ));
-                        }
-
-                        shared_resources = Some(util::parse_shared_resources(&content)?);
+
+        util::parse_arguments(&content, |ident, content| {
+            match &*ident.to_string() {
+                "core" => {
+                    if core.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
                     }
 
-                    "local" => {
-                        if local_resources.is_some() {
-                            return Err(parse::Error::new(
-                                ident.span(),
-                                "argument appears more than once",
-                            ));
-                        }
+                    core = Some(util::parse_core(content)?);
+                }
 
-                        local_resources = Some(util::parse_local_resources(&content)?);
+                "shared" => {
+                    if shared_resources.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
                     }
 
-                    _ => {
-                        return Err(parse::Error::new(ident.span(), "unexpected argument"));
-                    }
+                    shared_resources = Some(util::parse_shared_resources(content)?);
                 }
-                if content.is_empty() {
-                    break;
+
+                "local" => {
+                    if local_resources.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
+                    }
+
+                    local_resources = Some(util::parse_local_resources(content)?);
                 }
 
-                // Handle comma: ,
-                let _: Token![,] = content.parse()?;
+                _ => {
+                    return Err(parse::Error::new(ident.span(), "unexpected argument"));
+                }
             }
-        }
+
+            Ok(())
+        })?;
 
         Ok(IdleArgs {
+            core: core.unwrap_or(0),
             shared_resources: shared_resources.unwrap_or_default(),
             local_resources: local_resources.unwrap_or_default(),
         })
@@ -191,6 +226,7 @@ fn idle_args(tokens: TokenStream2) -> parse::Result<IdleArgs> {
 fn task_args(
     tokens: TokenStream2,
     settings: &Settings,
+    consts: &Consts,
 ) -> parse::Result<Either<HardwareTaskArgs, SoftwareTaskArgs>> {
     (|input: ParseStream<'_>| -> parse::Result<Either<HardwareTaskArgs, SoftwareTaskArgs>> {
         if input.is_empty() {
@@ -200,22 +236,20 @@ fn task_args(
         let mut binds = None;
         let mut capacity = None;
         let mut priority = None;
+        let mut priority_span = None;
+        let mut threshold = None;
+        let mut core = None;
+        let mut wcet = None;
+        let mut period = None;
+        let mut deadline = None;
+        let mut deadline_span = None;
         let mut shared_resources = None;
         let mut local_resources = None;
 
-
         let content;
         parenthesized!(content in input);
-        loop {
-            if content.is_empty() {
-                break;
-            }
-
-            // Parse identifier name
-            let ident: Ident = content.parse()?;
-            // Handle equal sign
-            let _: Token![=] = content.parse()?;
 
+        util::parse_arguments(&content, |ident, content| {
             let ident_s = ident.to_string();
             match &*ident_s {
                 "binds" if !settings.parse_binds => {
@@ -261,54 +295,62 @@ fn task_args(
                         ));
                     }
 
-                    // #lit
-                    let lit: LitInt = content.parse()?;
+                    capacity = Some(consts.parse_u8(content)?);
+                }
 
-                    if !lit.suffix().is_empty() {
+                "priority" => {
+                    if priority.is_some() {
                         return Err(parse::Error::new(
-                            lit.span(),
-                            "this literal must be unsuffixed",
+                            ident.span(),
+                            "argument appears more than once",
                         ));
                     }
 
-                    let value = lit.base10_parse::<u8>().ok();
-                    if value.is_none() || value == Some(0) {
+                    priority = Some(consts.parse_u8(content)?);
+                    priority_span = Some(ident.span());
+                }
+
+                "threshold" => {
+                    if threshold.is_some() {
                         return Err(parse::Error::new(
-                            lit.span(),
-                            "this literal must be in the range 1...255",
+                            ident.span(),
+                            "argument appears more than once",
                         ));
                     }
 
-                    capacity = Some(value.unwrap());
+                    threshold = Some((ident.span(), consts.parse_u8(content)?));
                 }
 
-                "priority" => {
-                    if priority.is_some() {
+                "core" => {
+                    if core.is_some() {
                         return Err(parse::Error::new(
                             ident.span(),
                             "argument appears more than once",
                         ));
                     }
 
-                    // #lit
-                    let lit: LitInt = content.parse()?;
+                    core = Some(util::parse_core(content)?);
+                }
 
-                    if !lit.suffix().is_empty() {
-                        return Err(parse::Error::new(
-                            lit.span(),
-                            "this literal must be unsuffixed",
-                        ));
-                    }
+                "wcet" | "period" | "deadline" => {
+                    let value = match &*ident_s {
+                        "wcet" => &mut wcet,
+                        "period" => &mut period,
+                        _ => &mut deadline,
+                    };
 
-                    let value = lit.base10_parse::<u8>().ok();
-                    if value.is_none() || value == Some(0) {
+                    if value.is_some() {
                         return Err(parse::Error::new(
-                            lit.span(),
-                            "this literal must be in the range 1...255",
+                            ident.span(),
+                            "argument appears more than once",
                         ));
                     }
 
-                    priority = Some(value.unwrap());
+                    *value = Some(util::parse_duration(content)?);
+
+                    if ident_s == "deadline" {
+                        deadline_span = Some(ident.span());
+                    }
                 }
 
                 "shared" => {
@@ -319,7 +361,7 @@ fn task_args(
                         ));
                     }
 
-                    shared_resources = Some(util::parse_shared_resources(&content)?);
+                    shared_resources = Some(util::parse_shared_resources(content)?);
                 }
 
                 "local" => {
@@ -330,7 +372,7 @@ fn task_args(
                         ));
                     }
 
-                    local_resources = Some(util::parse_local_resources(&content)?);
+                    local_resources = Some(util::parse_local_resources(content)?);
                 }
 
                 _ => {
@@ -338,21 +380,53 @@ fn task_args(
                 }
             }
 
-            if content.is_empty() {
-                break;
-            }
+            Ok(())
+        })?;
 
-            // Handle comma: ,
-            let _: Token![,] = content.parse()?;
-        }
         let priority = priority.unwrap_or(1);
+        let threshold = match threshold {
+            Some((span, threshold)) if threshold < priority => {
+                return Err(parse::Error::new(
+                    span,
+                    format!(
+                        "the preemption `threshold` ({}) can't be lower than the task `priority` ({})",
+                        threshold, priority
+                    ),
+                ));
+            }
+            threshold => threshold.map(|(_, threshold)| threshold),
+        };
+        let core = core.unwrap_or(0);
+        // The response-time analysis only considers one job of a task at a time
+        if let (Some(span), Some(deadline), Some(period)) = (deadline_span, deadline, period) {
+            if deadline > period {
+                return Err(parse::Error::new(
+                    span,
+                    format!(
+                        "the `deadline` ({}) can't be longer than the `period` ({})",
+                        crate::schedule::duration(deadline),
+                        crate::schedule::duration(period)
+                    ),
+                ));
+            }
+        }
+        let timing = Timing {
+            wcet,
+            period,
+            deadline,
+        };
         let shared_resources = shared_resources.unwrap_or_default();
         let local_resources = local_resources.unwrap_or_default();
 
         Ok(if let Some(binds) = binds {
+            check_fixed_priority(&binds, priority_span, settings)?;
+
             Either::Left(HardwareTaskArgs {
                 binds,
                 priority,
+                threshold,
+                core,
+                timing,
                 shared_resources,
                 local_resources,
             })
@@ -360,6 +434,9 @@ fn task_args(
             Either::Right(SoftwareTaskArgs {
                 capacity: capacity.unwrap_or(1),
                 priority,
+                threshold,
+                core,
+                timing,
                 shared_resources,
                 local_resources,
             })
@@ -368,10 +445,37 @@ fn task_args(
     .parse2(tokens)
 }
 
-fn monotonic_args(path: Path, tokens: TokenStream2) -> parse::Result<MonotonicArgs> {
+/// Rejects an explicit `priority` on something bound to a fixed-priority exception
+fn check_fixed_priority(
+    binds: &Ident,
+    priority_span: Option<Span>,
+    settings: &Settings,
+) -> parse::Result<()> {
+    match priority_span {
+        Some(span) if device::binding(binds, settings) == Binding::FixedPriorityException => {
+            Err(parse::Error::new(
+                span,
+                format!(
+                    "`{}` is an exception with a fixed priority, it can't take a `priority`",
+                    binds
+                ),
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn monotonic_args(
+    path: Path,
+    tokens: TokenStream2,
+    settings: &Settings,
+    consts: &Consts,
+) -> parse::Result<MonotonicArgs> {
     (|input: ParseStream<'_>| -> parse::Result<MonotonicArgs> {
         let mut binds = None;
         let mut priority = None;
+        let mut priority_span = None;
+        let mut core = None;
         let mut default = None;
 
         if !input.peek(token::Paren) {
@@ -384,80 +488,63 @@ fn monotonic_args(path: Path, tokens: TokenStream2) -> parse::Result<MonotonicAr
         let content;
         parenthesized!(content in input);
 
-        if !content.is_empty() {
-            loop {
-                // Parse identifier name
-                let ident: Ident = content.parse()?;
-                // Handle equal sign
-                let _: Token![=] = content.parse()?;
-
-                match &*ident.to_string() {
-                    "binds" => {
-                        if binds.is_some() {
-                            return Err(parse::Error::new(
-                                ident.span(),
-                                "argument appears more than once",
-                            ));
-                        }
-                        // Parse identifier name
-                        let ident = content.parse()?;
-
-                        binds = Some(ident);
+        util::parse_arguments(&content, |ident, content| {
+            match &*ident.to_string() {
+                "binds" => {
+                    if binds.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
                     }
+                    // Parse identifier name
+                    let ident = content.parse()?;
+
+                    binds = Some(ident);
+                }
 
-                    "priority" => {
-                        if priority.is_some() {
-                            return Err(parse::Error::new(
-                                ident.span(),
-                                "argument appears more than once",
-                            ));
-                        }
-
-                        // #lit
-                        let lit: LitInt = content.parse()?;
-
-                        if !lit.suffix().is_empty() {
-                            return Err(parse::Error::new(
-                                lit.span(),
-                                "this literal must be unsuffixed",
-                            ));
-                        }
-
-                        let value = lit.base10_parse::<u8>().ok();
-                        if value.is_none() || value == Some(0) {
-                            return Err(parse::Error::new(
-                                lit.span(),
-                                "this literal must be in the range 1...255",
-                            ));
-                        }
-
-                        priority = Some(value.unwrap());
+                "priority" => {
+                    if priority.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
                     }
 
-                    "default" => {
-                        if default.is_some() {
-                            return Err(parse::Error::new(
-                                ident.span(),
-                                "argument appears more than once",
-                            ));
-                        }
+                    priority = Some(consts.parse_u8(content)?);
+                    priority_span = Some(ident.span());
+                }
 
-                        let lit: LitBool = content.parse()?;
-                        default = Some(lit.value);
+                "core" => {
+                    if core.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
                     }
 
-                    _ => {
-                        return Err(parse::Error::new(ident.span(), "unexpected argument"));
-                    }
+                    core = Some(util::parse_core(content)?);
                 }
-                if content.is_empty() {
-                    break;
+
+                "default" => {
+                    if default.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
+                    }
+
+                    let lit: LitBool = content.parse()?;
+                    default = Some(lit.value);
                 }
 
-                // Handle comma: ,
-                let _: Token![,] = content.parse()?;
+                _ => {
+                    return Err(parse::Error::new(ident.span(), "unexpected argument"));
+                }
             }
-        }
+
+            Ok(())
+        })?;
 
         let binds = if let Some(r) = binds {
             r
@@ -467,11 +554,13 @@ fn monotonic_args(path: Path, tokens: TokenStream2) -> parse::Result<MonotonicAr
                 "`binds = ...` is missing",
             ));
         };
+        check_fixed_priority(&binds, priority_span, settings)?;
         let default = default.unwrap_or(false);
 
         Ok(MonotonicArgs {
             binds,
             priority,
+            core: core.unwrap_or(0),
             default,
         })
     })
@@ -5,15 +5,16 @@ use proc_macro2::TokenStream as TokenStream2;
 use syn::{
     parse::{self, ParseStream, Parser},
     spanned::Spanned,
-    Expr, ExprArray, Fields, ForeignItem, Ident, Item, LitBool, Path, Token, Type, Visibility,
+    Expr, ExprArray, Fields, ForeignItem, Ident, Item, LitBool, LitInt, Path, Type, Visibility,
 };
 
-use super::Input;
+use super::{Consts, Input};
 use crate::{
     ast::{
         App, AppArgs, ExternInterrupt, ExternInterrupts, HardwareTask, Idle, IdleArgs, Init,
-        InitArgs, LocalResource, Monotonic, MonotonicArgs, SharedResource, SoftwareTask,
+        InitArgs, LocalResource, Monotonic, MonotonicArgs, SharedResource, SoftwareTask, Tasks,
     },
+    cfg::Cfg,
     parse::util,
     Either, Map, Set, Settings,
 };
@@ -24,17 +25,11 @@ impl AppArgs {
             let mut custom = Set::new();
             let mut device = None;
             let mut peripherals = true;
+            let mut cores = 1;
             let mut extern_interrupts = ExternInterrupts::new();
+            let mut dispatchers_span = None;
 
-            loop {
-                if input.is_empty() {
-                    break;
-                }
-
-                // #ident = ..
-                let ident: Ident = input.parse()?;
-                let _eq_token: Token![=] = input.parse()?;
-
+            util::parse_arguments(input, |ident, input| {
                 if custom.contains(&ident) {
                     return Err(parse::Error::new(
                         ident.span(),
@@ -69,25 +64,49 @@ impl AppArgs {
                         }
                     }
 
+                    "cores" => {
+                        let lit: LitInt = input.parse()?;
+
+                        if !lit.suffix().is_empty() {
+                            return Err(parse::Error::new(
+                                lit.span(),
+                                "this literal must be unsuffixed",
+                            ));
+                        }
+
+                        let value = lit.base10_parse::<u8>().ok();
+                        if value.is_none() || value == Some(0) {
+                            return Err(parse::Error::new(
+                                lit.span(),
+                                "this literal must be in the range 1...255",
+                            ));
+                        }
+
+                        cores = value.unwrap();
+                    }
+
                     "dispatchers" => {
                         if let Ok(p) = input.parse::<ExprArray>() {
+                            dispatchers_span = Some(p.bracket_token.span);
+                            let mut errors = vec![];
+
                             for e in p.elems {
                                 match e {
                                     Expr::Path(ep) => {
                                         let path = ep.path;
-                                        let ident = if path.leading_colon.is_some()
-                                            || path.segments.len() != 1
+                                        if path.leading_colon.is_some() || path.segments.len() != 1
                                         {
-                                            return Err(parse::Error::new(
+                                            errors.push(parse::Error::new(
                                                 path.span(),
                                                 "interrupt must be an identifier, not a path",
                                             ));
-                                        } else {
-                                            path.segments[0].ident.clone()
-                                        };
+                                            continue;
+                                        }
+
+                                        let ident = path.segments[0].ident.clone();
                                         let span = ident.span();
                                         if extern_interrupts.contains_key(&ident) {
-                                            return Err(parse::Error::new(
+                                            errors.push(parse::Error::new(
                                                 span,
                                                 "this extern interrupt is listed more than once",
                                             ));
@@ -97,13 +116,15 @@ impl AppArgs {
                                         }
                                     }
                                     _ => {
-                                        return Err(parse::Error::new(
+                                        errors.push(parse::Error::new(
                                             e.span(),
                                             "interrupt must be an identifier",
                                         ));
                                     }
                                 }
                             }
+
+                            crate::combine_errors(errors)?;
                         } else {
                             return Err(parse::Error::new(
                                 ident.span(),
@@ -117,18 +138,15 @@ impl AppArgs {
                     }
                 }
 
-                if input.is_empty() {
-                    break;
-                }
-
-                // ,
-                let _: Token![,] = input.parse()?;
-            }
+                Ok(())
+            })?;
 
             Ok(AppArgs {
                 device,
                 peripherals,
+                cores,
                 extern_interrupts,
+                dispatchers_span,
             })
         })
         .parse2(tokens)
@@ -138,6 +156,7 @@ impl AppArgs {
 impl App {
     pub(crate) fn parse(args: AppArgs, input: Input, settings: &Settings) -> parse::Result<Self> {
         let mut init = None;
+        let mut init_defined = false;
         let mut idle = None;
 
         let mut shared_resources_ident = None;
@@ -145,36 +164,44 @@ impl App {
         let mut local_resources_ident = None;
         let mut local_resources = Map::new();
         let mut monotonics = Map::new();
-        let mut hardware_tasks = Map::new();
-        let mut software_tasks = Map::new();
+        let mut hardware_tasks = Tasks::new();
+        let mut software_tasks = Tasks::new();
         let mut user_imports = vec![];
         let mut user_code = vec![];
 
-        let mut seen_idents = HashSet::<Ident>::new();
-        let mut bindings = HashSet::<Ident>::new();
+        let mut seen_idents = Vec::<(Ident, Cfg)>::new();
+        let mut bindings = Vec::<(Ident, Cfg)>::new();
         let mut monotonic_types = HashSet::<Type>::new();
 
-        let mut check_binding = |ident: &Ident| {
-            if bindings.contains(ident) {
+        // Items under mutually exclusive `#[cfg]`s are never compiled together, so they may reuse
+        // an interrupt or a name
+        let mut check_binding = |ident: &Ident, cfg: &Cfg| {
+            if bindings
+                .iter()
+                .any(|(other, other_cfg)| other == ident && other_cfg.overlaps(cfg))
+            {
                 return Err(parse::Error::new(
                     ident.span(),
                     "this interrupt is already bound",
                 ));
             } else {
-                bindings.insert(ident.clone());
+                bindings.push((ident.clone(), cfg.clone()));
             }
 
             Ok(())
         };
 
-        let mut check_ident = |ident: &Ident| {
-            if seen_idents.contains(ident) {
+        let mut check_ident = |ident: &Ident, cfg: &Cfg| {
+            if seen_idents
+                .iter()
+                .any(|(other, other_cfg)| other == ident && other_cfg.overlaps(cfg))
+            {
                 return Err(parse::Error::new(
                     ident.span(),
                     "this identifier has already been used",
                 ));
             } else {
-                seen_idents.insert(ident.clone());
+                seen_idents.push((ident.clone(), cfg.clone()));
             }
 
             Ok(())
@@ -193,329 +220,391 @@ impl App {
             Ok(())
         };
 
-        for mut item in input.items {
-            match item {
-                Item::Fn(mut item) => {
-                    let span = item.sig.ident.span();
-                    if let Some(pos) = item
-                        .attrs
-                        .iter()
-                        .position(|attr| util::attr_eq(attr, "init"))
-                    {
-                        let args = InitArgs::parse(item.attrs.remove(pos).tokens)?;
-
-                        // If an init function already exists, error
-                        if init.is_some() {
-                            return Err(parse::Error::new(
-                                span,
-                                "`#[init]` function must appear at most once",
-                            ));
-                        }
-
-                        check_ident(&item.sig.ident)?;
+        // `const` items can be used in task arguments, even before they are declared
+        let consts = Consts::collect(&input.items);
+
+        let mut errors = vec![];
+
+        for item in input.items {
+            // An error in one item doesn't stop the parsing of the following ones
+            let result = (|| -> parse::Result<()> {
+                let mut item = item;
+                match item {
+                    Item::Fn(mut item) => {
+                        let span = item.sig.ident.span();
+                        if let Some(pos) = item
+                            .attrs
+                            .iter()
+                            .position(|attr| util::attr_eq(attr, "init"))
+                        {
+                            // If an init function already exists, error
+                            if init_defined {
+                                return Err(parse::Error::new(
+                                    span,
+                                    "`#[init]` function must appear at most once",
+                                ));
+                            }
 
-                        init = Some(Init::parse(args, item)?);
-                    } else if let Some(pos) = item
-                        .attrs
-                        .iter()
-                        .position(|attr| util::attr_eq(attr, "idle"))
-                    {
-                        let args = IdleArgs::parse(item.attrs.remove(pos).tokens)?;
+                            init_defined = true;
 
-                        // If an idle function already exists, error
-                        if idle.is_some() {
-                            return Err(parse::Error::new(
-                                span,
-                                "`#[idle]` function must appear at most once",
-                            ));
-                        }
+                            let args = InitArgs::parse(item.attrs.remove(pos).tokens)?;
 
-                        check_ident(&item.sig.ident)?;
+                            check_ident(&item.sig.ident, &Cfg::from_attrs(&item.attrs))?;
 
-                        idle = Some(Idle::parse(args, item)?);
-                    } else if let Some(pos) = item
-                        .attrs
-                        .iter()
-                        .position(|attr| util::attr_eq(attr, "task"))
-                    {
-                        if hardware_tasks.contains_key(&item.sig.ident)
-                            || software_tasks.contains_key(&item.sig.ident)
+                            init = Some(Init::parse(args, item)?);
+                        } else if let Some(pos) = item
+                            .attrs
+                            .iter()
+                            .position(|attr| util::attr_eq(attr, "idle"))
                         {
-                            return Err(parse::Error::new(
-                                span,
-                                "this task is defined multiple times",
-                            ));
-                        }
+                            let args = IdleArgs::parse(item.attrs.remove(pos).tokens)?;
 
-                        match crate::parse::task_args(item.attrs.remove(pos).tokens, settings)? {
-                            Either::Left(args) => {
-                                check_binding(&args.binds)?;
-                                check_ident(&item.sig.ident)?;
-
-                                hardware_tasks.insert(
-                                    item.sig.ident.clone(),
-                                    HardwareTask::parse(args, item)?,
-                                );
+                            // If an idle function already exists, error
+                            if idle.is_some() {
+                                return Err(parse::Error::new(
+                                    span,
+                                    "`#[idle]` function must appear at most once",
+                                ));
                             }
 
-                            Either::Right(args) => {
-                                check_ident(&item.sig.ident)?;
+                            check_ident(&item.sig.ident, &Cfg::from_attrs(&item.attrs))?;
+
+                            idle = Some(Idle::parse(args, item, settings)?);
+                        } else if let Some(pos) = item
+                            .attrs
+                            .iter()
+                            .position(|attr| util::attr_eq(attr, "task"))
+                        {
+                            let cfg = Cfg::from_attrs(&item.attrs);
+
+                            check_task_name(
+                                &item.sig.ident,
+                                &cfg,
+                                &hardware_tasks,
+                                &software_tasks,
+                            )?;
+
+                            match crate::parse::task_args(
+                                item.attrs.remove(pos).tokens,
+                                settings,
+                                &consts,
+                            )? {
+                                Either::Left(args) => {
+                                    check_binding(&args.binds, &cfg)?;
+                                    check_ident(&item.sig.ident, &cfg)?;
+
+                                    hardware_tasks.insert(
+                                        item.sig.ident.clone(),
+                                        HardwareTask::parse(args, item, settings)?,
+                                    );
+                                }
+
+                                Either::Right(args) => {
+                                    check_ident(&item.sig.ident, &cfg)?;
 
-                                software_tasks.insert(
-                                    item.sig.ident.clone(),
-                                    SoftwareTask::parse(args, item)?,
-                                );
+                                    software_tasks.insert(
+                                        item.sig.ident.clone(),
+                                        SoftwareTask::parse(args, item, settings)?,
+                                    );
+                                }
                             }
+                        } else {
+                            // Forward normal functions
+                            user_code.push(Item::Fn(item.clone()));
                         }
-                    } else {
-                        // Forward normal functions
-                        user_code.push(Item::Fn(item.clone()));
                     }
-                }
 
-                Item::Struct(ref mut struct_item) => {
-                    // Match structures with the attribute #[shared], name of structure is not
-                    // important
-                    if let Some(_pos) = struct_item
-                        .attrs
-                        .iter()
-                        .position(|attr| util::attr_eq(attr, "shared"))
-                    {
-                        let span = struct_item.ident.span();
+                    Item::Struct(ref mut struct_item) => {
+                        // Match structures with the attribute #[shared], name of structure is not
+                        // important
+                        if let Some(_pos) = struct_item
+                            .attrs
+                            .iter()
+                            .position(|attr| util::attr_eq(attr, "shared"))
+                        {
+                            let span = struct_item.ident.span();
 
-                        shared_resources_ident = Some(struct_item.ident.clone());
+                            shared_resources_ident = Some(struct_item.ident.clone());
 
-                        if !shared_resources.is_empty() {
-                            return Err(parse::Error::new(
-                                span,
-                                "`#[shared]` struct must appear at most once",
-                            ));
-                        }
+                            if !shared_resources.is_empty() {
+                                return Err(parse::Error::new(
+                                    span,
+                                    "`#[shared]` struct must appear at most once",
+                                ));
+                            }
 
-                        if struct_item.vis != Visibility::Inherited {
-                            return Err(parse::Error::new(
-                                struct_item.span(),
-                                "this item must have inherited / private visibility",
-                            ));
-                        }
+                            if struct_item.vis != Visibility::Inherited {
+                                return Err(parse::Error::new(
+                                    struct_item.span(),
+                                    "this item must have inherited / private visibility",
+                                ));
+                            }
 
-                        if let Fields::Named(fields) = &mut struct_item.fields {
-                            for field in &mut fields.named {
-                                let ident = field.ident.as_ref().expect("UNREACHABLE");
+                            if let Fields::Named(fields) = &mut struct_item.fields {
+                                for field in &mut fields.named {
+                                    let ident = field.ident.as_ref().expect("UNREACHABLE");
 
-                                if shared_resources.contains_key(ident) {
-                                    return Err(parse::Error::new(
-                                        ident.span(),
-                                        "this resource is listed more than once",
-                                    ));
-                                }
+                                    if shared_resources.contains_key(ident) {
+                                        return Err(parse::Error::new(
+                                            ident.span(),
+                                            "this resource is listed more than once",
+                                        ));
+                                    }
 
-                                shared_resources.insert(
-                                    ident.clone(),
-                                    SharedResource::parse(field, ident.span())?,
-                                );
+                                    shared_resources.insert(
+                                        ident.clone(),
+                                        SharedResource::parse(field, ident.span())?,
+                                    );
+                                }
+                            } else {
+                                return Err(parse::Error::new(
+                                    struct_item.span(),
+                                    "this `struct` must have named fields",
+                                ));
                             }
-                        } else {
-                            return Err(parse::Error::new(
-                                struct_item.span(),
-                                "this `struct` must have named fields",
-                            ));
-                        }
-                    } else if let Some(_pos) = struct_item
-                        .attrs
-                        .iter()
-                        .position(|attr| util::attr_eq(attr, "local"))
-                    {
-                        let span = struct_item.ident.span();
+                        } else if let Some(_pos) = struct_item
+                            .attrs
+                            .iter()
+                            .position(|attr| util::attr_eq(attr, "local"))
+                        {
+                            let span = struct_item.ident.span();
 
-                        local_resources_ident = Some(struct_item.ident.clone());
+                            local_resources_ident = Some(struct_item.ident.clone());
 
-                        if !local_resources.is_empty() {
-                            return Err(parse::Error::new(
-                                span,
-                                "`#[local]` struct must appear at most once",
-                            ));
-                        }
+                            if !local_resources.is_empty() {
+                                return Err(parse::Error::new(
+                                    span,
+                                    "`#[local]` struct must appear at most once",
+                                ));
+                            }
 
-                        if struct_item.vis != Visibility::Inherited {
-                            return Err(parse::Error::new(
-                                struct_item.span(),
-                                "this item must have inherited / private visibility",
-                            ));
-                        }
+                            if struct_item.vis != Visibility::Inherited {
+                                return Err(parse::Error::new(
+                                    struct_item.span(),
+                                    "this item must have inherited / private visibility",
+                                ));
+                            }
 
-                        if let Fields::Named(fields) = &mut struct_item.fields {
-                            for field in &mut fields.named {
-                                let ident = field.ident.as_ref().expect("UNREACHABLE");
+                            if let Fields::Named(fields) = &mut struct_item.fields {
+                                for field in &mut fields.named {
+                                    let ident = field.ident.as_ref().expect("UNREACHABLE");
 
-                                if local_resources.contains_key(ident) {
-                                    return Err(parse::Error::new(
-                                        ident.span(),
-                                        "this resource is listed more than once",
-                                    ));
-                                }
+                                    if local_resources.contains_key(ident) {
+                                        return Err(parse::Error::new(
+                                            ident.span(),
+                                            "this resource is listed more than once",
+                                        ));
+                                    }
 
-                                local_resources.insert(
-                                    ident.clone(),
-                                    LocalResource::parse(field, ident.span())?,
-                                );
+                                    local_resources.insert(
+                                        ident.clone(),
+                                        LocalResource::parse(field, ident.span())?,
+                                    );
+                                }
+                            } else {
+                                return Err(parse::Error::new(
+                                    struct_item.span(),
+                                    "this `struct` must have named fields",
+                                ));
                             }
                         } else {
-                            return Err(parse::Error::new(
-                                struct_item.span(),
-                                "this `struct` must have named fields",
-                            ));
+                            // Structure without the #[resources] attribute should just be passed along
+                            user_code.push(item.clone());
                         }
-                    } else {
-                        // Structure without the #[resources] attribute should just be passed along
-                        user_code.push(item.clone());
                     }
-                }
 
-                Item::ForeignMod(mod_) => {
-                    if !util::abi_is_rust(&mod_.abi) {
-                        return Err(parse::Error::new(
-                            mod_.abi.extern_token.span(),
-                            "this `extern` block must use the \"Rust\" ABI",
-                        ));
-                    }
+                    Item::ForeignMod(mod_) => {
+                        if !util::abi_is_rust(&mod_.abi) {
+                            return Err(parse::Error::new(
+                                mod_.abi.extern_token.span(),
+                                "this `extern` block must use the \"Rust\" ABI",
+                            ));
+                        }
 
-                    for item in mod_.items {
-                        if let ForeignItem::Fn(mut item) = item {
-                            let span = item.sig.ident.span();
-                            if let Some(pos) = item
-                                .attrs
-                                .iter()
-                                .position(|attr| util::attr_eq(attr, "task"))
-                            {
-                                if hardware_tasks.contains_key(&item.sig.ident)
-                                    || software_tasks.contains_key(&item.sig.ident)
-                                {
-                                    return Err(parse::Error::new(
-                                        span,
-                                        "this task is defined multiple times",
-                                    ));
-                                }
+                        for item in mod_.items {
+                            let result = (|| -> parse::Result<()> {
+                                if let ForeignItem::Fn(mut item) = item {
+                                    let span = item.sig.ident.span();
+                                    if let Some(pos) = item
+                                        .attrs
+                                        .iter()
+                                        .position(|attr| util::attr_eq(attr, "task"))
+                                    {
+                                        let cfg = Cfg::from_attrs(&item.attrs);
+
+                                        check_task_name(
+                                            &item.sig.ident,
+                                            &cfg,
+                                            &hardware_tasks,
+                                            &software_tasks,
+                                        )?;
+
+                                        if item.attrs.len() != 1 {
+                                            return Err(parse::Error::new(
+                                                span,
+                                                "`extern` task required `#[task(..)]` attribute",
+                                            ));
+                                        }
 
-                                if item.attrs.len() != 1 {
+                                        match crate::parse::task_args(
+                                            item.attrs.remove(pos).tokens,
+                                            settings,
+                                            &consts,
+                                        )? {
+                                            Either::Left(args) => {
+                                                check_binding(&args.binds, &cfg)?;
+                                                check_ident(&item.sig.ident, &cfg)?;
+
+                                                hardware_tasks.insert(
+                                                    item.sig.ident.clone(),
+                                                    HardwareTask::parse_foreign(
+                                                        args, item, settings,
+                                                    )?,
+                                                );
+                                            }
+
+                                            Either::Right(args) => {
+                                                check_ident(&item.sig.ident, &cfg)?;
+
+                                                software_tasks.insert(
+                                                    item.sig.ident.clone(),
+                                                    SoftwareTask::parse_foreign(
+                                                        args, item, settings,
+                                                    )?,
+                                                );
+                                            }
+                                        }
+                                    } else {
+                                        return Err(parse::Error::new(
+                                            span,
+                                            "`extern` task required `#[task(..)]` attribute",
+                                        ));
+                                    }
+                                } else {
                                     return Err(parse::Error::new(
-                                        span,
-                                        "`extern` task required `#[task(..)]` attribute",
+                                        item.span(),
+                                        "this item must live outside the `#[app]` module",
                                     ));
                                 }
 
-                                match crate::parse::task_args(
-                                    item.attrs.remove(pos).tokens,
-                                    settings,
-                                )? {
-                                    Either::Left(args) => {
-                                        check_binding(&args.binds)?;
-                                        check_ident(&item.sig.ident)?;
-
-                                        hardware_tasks.insert(
-                                            item.sig.ident.clone(),
-                                            HardwareTask::parse_foreign(args, item)?,
-                                        );
-                                    }
-
-                                    Either::Right(args) => {
-                                        check_ident(&item.sig.ident)?;
+                                Ok(())
+                            })();
 
-                                        software_tasks.insert(
-                                            item.sig.ident.clone(),
-                                            SoftwareTask::parse_foreign(args, item)?,
-                                        );
-                                    }
-                                }
-                            } else {
-                                return Err(parse::Error::new(
-                                    span,
-                                    "`extern` task required `#[task(..)]` attribute",
-                                ));
+                            if let Err(e) = result {
+                                errors.push(e);
                             }
-                        } else {
-                            return Err(parse::Error::new(
-                                item.span(),
-                                "this item must live outside the `#[app]` module",
-                            ));
                         }
                     }
-                }
-                Item::Use(itemuse_) => {
-                    // Store the user provided use-statements
-                    user_imports.push(itemuse_.clone());
-                }
-                Item::Type(ref mut type_item) => {
-                    // Match types with the attribute #[monotonic]
-                    if let Some(pos) = type_item
-                        .attrs
-                        .iter()
-                        .position(|attr| util::attr_eq(attr, "monotonic"))
-                    {
-                        let span = type_item.ident.span();
-
-                        if monotonics.contains_key(&type_item.ident) {
-                            return Err(parse::Error::new(
+                    Item::Use(itemuse_) => {
+                        // Store the user provided use-statements
+                        user_imports.push(itemuse_.clone());
+                    }
+                    Item::Type(ref mut type_item) => {
+                        // Match types with the attribute #[monotonic]
+                        if let Some(pos) = type_item
+                            .attrs
+                            .iter()
+                            .position(|attr| util::attr_eq(attr, "monotonic"))
+                        {
+                            let span = type_item.ident.span();
+
+                            if monotonics.contains_key(&type_item.ident) {
+                                return Err(parse::Error::new(
                                 span,
                                 "`#[monotonic(...)]` on a specific type must appear at most once",
                             ));
-                        }
+                            }
 
-                        if type_item.vis != Visibility::Inherited {
-                            return Err(parse::Error::new(
-                                type_item.span(),
-                                "this item must have inherited / private visibility",
-                            ));
-                        }
+                            if type_item.vis != Visibility::Inherited {
+                                return Err(parse::Error::new(
+                                    type_item.span(),
+                                    "this item must have inherited / private visibility",
+                                ));
+                            }
 
-                        check_monotonic(&type_item.ty)?;
+                            check_monotonic(&type_item.ty)?;
 
-                        let m = type_item.attrs.remove(pos);
-                        let args = MonotonicArgs::parse(m)?;
+                            let m = type_item.attrs.remove(pos);
+                            let args = MonotonicArgs::parse(m, settings, &consts)?;
 
-                        check_binding(&args.binds)?;
+                            check_binding(&args.binds, &Cfg::from_attrs(&type_item.attrs))?;
 
-                        let monotonic = Monotonic::parse(args, type_item, span)?;
+                            let monotonic = Monotonic::parse(args, type_item, span, settings)?;
 
-                        monotonics.insert(type_item.ident.clone(), monotonic);
-                    }
+                            monotonics.insert(type_item.ident.clone(), monotonic);
+                        }
 
-                    // All types are passed on
-                    user_code.push(item.clone());
-                }
-                _ => {
-                    // Anything else within the module should not make any difference
-                    user_code.push(item.clone());
+                        // All types are passed on
+                        user_code.push(item.clone());
+                    }
+                    _ => {
+                        // Anything else within the module should not make any difference
+                        user_code.push(item.clone());
+                    }
                 }
+
+                Ok(())
+            })();
+
+            if let Err(e) = result {
+                errors.push(e);
             }
         }
 
-        let shared_resources_ident =
-            shared_resources_ident.expect("No `#[shared]` resource struct defined");
-        let local_resources_ident =
-            local_resources_ident.expect("No `#[local]` resource struct defined");
-        let init = init.expect("No `#[init]` function defined");
-
-        if shared_resources_ident != init.user_shared_struct {
-            return Err(parse::Error::new(
-                init.user_shared_struct.span(),
-                format!(
-                    "This name and the one defined on `#[shared]` are not the same. Should this be `{}`?",
-                    shared_resources_ident
-                ),
+        // Only report missing items if everything else parsed fine, a module with errors is
+        // likely a work in progress
+        crate::combine_errors(errors)?;
+        let mut errors = vec![];
+
+        if shared_resources_ident.is_none() {
+            errors.push(parse::Error::new(
+                input.ident.span(),
+                "no `#[shared]` resource struct defined",
             ));
         }
 
-        if local_resources_ident != init.user_local_struct {
-            return Err(parse::Error::new(
-                init.user_local_struct.span(),
-                format!(
-                    "This name and the one defined on `#[local]` are not the same. Should this be `{}`?",
-                    local_resources_ident
-                ),
+        if local_resources_ident.is_none() {
+            errors.push(parse::Error::new(
+                input.ident.span(),
+                "no `#[local]` resource struct defined",
             ));
         }
 
+        if !init_defined {
+            errors.push(parse::Error::new(
+                input.ident.span(),
+                "no `#[init]` function defined",
+            ));
+        }
+
+        if let (Some(init), Some(shared_resources_ident)) = (&init, &shared_resources_ident) {
+            if *shared_resources_ident != init.user_shared_struct {
+                errors.push(parse::Error::new(
+                    init.user_shared_struct.span(),
+                    format!(
+                        "This name and the one defined on `#[shared]` are not the same. Should this be `{}`?",
+                        shared_resources_ident
+                    ),
+                ));
+            }
+        }
+
+        if let (Some(init), Some(local_resources_ident)) = (&init, &local_resources_ident) {
+            if *local_resources_ident != init.user_local_struct {
+                errors.push(parse::Error::new(
+                    init.user_local_struct.span(),
+                    format!(
+                        "This name and the one defined on `#[local]` are not the same. Should this be `{}`?",
+                        local_resources_ident
+                    ),
+                ));
+            }
+        }
+
+        crate::combine_errors(errors)?;
+
+        let init = init.expect("UNREACHABLE");
+
         Ok(App {
             args,
             name: input.ident,
@@ -532,6 +621,29 @@ impl App {
     }
 }
 
+/// Variants of a task under mutually exclusive `#[cfg]`s can have the same name
+fn check_task_name(
+    name: &Ident,
+    cfg: &Cfg,
+    hardware_tasks: &Tasks<HardwareTask>,
+    software_tasks: &Tasks<SoftwareTask>,
+) -> parse::Result<()> {
+    let overlaps = hardware_tasks
+        .variants(name)
+        .map(|task| &task.cfgs)
+        .chain(software_tasks.variants(name).map(|task| &task.cfgs))
+        .any(|cfgs| Cfg::from_attrs(cfgs).overlaps(cfg));
+
+    if overlaps {
+        Err(parse::Error::new(
+            name.span(),
+            "this task is defined multiple times",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::ast::AppArgs;
@@ -0,0 +1,177 @@
+use syn::{
+    parse::{self, ParseStream},
+    spanned::Spanned,
+    BinOp, Expr, ExprLit, Ident, Item, Lit, UnOp,
+};
+
+use crate::{Map, Set};
+
+/// The `const` items of the `#[app]` module, used to evaluate task arguments like
+/// `priority = BASE + 1`
+pub(crate) struct Consts {
+    items: Map<Expr>,
+    /// The `const` items defined more than once, e.g. under different `#[cfg]`s
+    duplicated: Set<Ident>,
+}
+
+impl Consts {
+    pub(crate) fn collect(items: &[Item]) -> Self {
+        let mut consts = Consts {
+            items: Map::new(),
+            duplicated: Set::new(),
+        };
+
+        for item in items {
+            if let Item::Const(item) = item {
+                if consts
+                    .items
+                    .insert(item.ident.clone(), (*item.expr).clone())
+                    .is_some()
+                {
+                    consts.duplicated.insert(item.ident.clone());
+                }
+            }
+        }
+
+        consts
+    }
+
+    /// Parses an argument that must evaluate to a value in the range 1...255
+    ///
+    /// The argument is either an unsuffixed integer literal or an expression made of integer
+    /// literals, `const` items of the `#[app]` module, parentheses and arithmetic operators
+    pub(crate) fn parse_u8(&self, content: ParseStream<'_>) -> parse::Result<u8> {
+        let expr: Expr = content.parse()?;
+
+        if let Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) = &expr
+        {
+            if !lit.suffix().is_empty() {
+                return Err(parse::Error::new(
+                    lit.span(),
+                    "this literal must be unsuffixed",
+                ));
+            }
+
+            return match lit.base10_parse::<u8>() {
+                Ok(value) if value != 0 => Ok(value),
+                _ => Err(parse::Error::new(
+                    lit.span(),
+                    "this literal must be in the range 1...255",
+                )),
+            };
+        }
+
+        let value = self.eval(&expr, &mut vec![])?;
+        if (1..=255).contains(&value) {
+            Ok(value as u8)
+        } else {
+            Err(parse::Error::new(
+                expr.span(),
+                format!(
+                    "this expression must evaluate to a value in the range 1...255 (it evaluates to {})",
+                    value
+                ),
+            ))
+        }
+    }
+
+    /// Evaluates `expr`; `stack` holds the `const` items being evaluated, to catch cycles
+    fn eval<'a>(&'a self, expr: &'a Expr, stack: &mut Vec<&'a Ident>) -> parse::Result<i128> {
+        match expr {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(lit), ..
+            }) => lit
+                .base10_parse::<i128>()
+                .map_err(|_| parse::Error::new(lit.span(), "this literal is out of range")),
+
+            Expr::Paren(paren) => self.eval(&paren.expr, stack),
+
+            Expr::Group(group) => self.eval(&group.expr, stack),
+
+            Expr::Path(path) if path.qself.is_none() && path.path.get_ident().is_some() => {
+                let ident = path.path.get_ident().expect("UNREACHABLE");
+                let (name, expr) = self.items.get_key_value(ident).ok_or_else(|| {
+                    parse::Error::new(
+                        ident.span(),
+                        format!("`{}` is not a `const` item of the `#[app]` module", ident),
+                    )
+                })?;
+
+                if self.duplicated.contains(name) {
+                    return Err(parse::Error::new(
+                        ident.span(),
+                        format!(
+                            "`{}` is defined more than once, e.g. under different `#[cfg]`s, so its \
+                             value is unknown",
+                            ident
+                        ),
+                    ));
+                }
+
+                if stack.contains(&name) {
+                    return Err(parse::Error::new(
+                        ident.span(),
+                        format!("the value of `{}` depends on itself", ident),
+                    ));
+                }
+
+                stack.push(name);
+                let value = self.eval(expr, stack);
+                stack.pop();
+
+                value
+            }
+
+            Expr::Unary(unary) if matches!(unary.op, UnOp::Neg(_)) => {
+                self.eval(&unary.expr, stack)?.checked_neg().ok_or_else(|| {
+                    parse::Error::new(
+                        unary.op.span(),
+                        "this operation overflows or divides by zero",
+                    )
+                })
+            }
+
+            Expr::Binary(binary) => {
+                let left = self.eval(&binary.left, stack)?;
+                let right = self.eval(&binary.right, stack)?;
+
+                let value = match binary.op {
+                    BinOp::Add(_) => left.checked_add(right),
+                    BinOp::Sub(_) => left.checked_sub(right),
+                    BinOp::Mul(_) => left.checked_mul(right),
+                    BinOp::Div(_) => left.checked_div(right),
+                    BinOp::Rem(_) => left.checked_rem(right),
+                    BinOp::Shl(_) => u32::try_from(right)
+                        .ok()
+                        .and_then(|right| left.checked_shl(right)),
+                    BinOp::Shr(_) => u32::try_from(right)
+                        .ok()
+                        .and_then(|right| left.checked_shr(right)),
+                    BinOp::BitAnd(_) => Some(left & right),
+                    BinOp::BitOr(_) => Some(left | right),
+                    BinOp::BitXor(_) => Some(left ^ right),
+                    _ => return Err(unsupported(expr)),
+                };
+
+                value.ok_or_else(|| {
+                    parse::Error::new(
+                        binary.op.span(),
+                        "this operation overflows or divides by zero",
+                    )
+                })
+            }
+
+            _ => Err(unsupported(expr)),
+        }
+    }
+}
+
+fn unsupported(expr: &Expr) -> parse::Error {
+    parse::Error::new(
+        expr.span(),
+        "this expression can't be evaluated; expected an integer literal, a `const` item of the \
+         `#[app]` module or arithmetic on those",
+    )
+}
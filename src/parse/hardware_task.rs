@@ -3,13 +3,19 @@ use syn::{parse, ForeignItemFn, ItemFn, Stmt};
 use crate::parse::util::FilterAttrs;
 use crate::{
     ast::{HardwareTask, HardwareTaskArgs},
+    device,
     parse::util,
+    Settings,
 };
 
 impl HardwareTask {
-    pub(crate) fn parse(args: HardwareTaskArgs, item: ItemFn) -> parse::Result<Self> {
+    pub(crate) fn parse(
+        args: HardwareTaskArgs,
+        item: ItemFn,
+        settings: &Settings,
+    ) -> parse::Result<Self> {
         let span = item.sig.ident.span();
-        let valid_signature = util::check_fn_signature(&item)
+        let valid_signature = util::check_fn_signature(&item, false)
             && item.sig.inputs.len() == 1
             && util::type_is_unit(&item.sig.output);
 
@@ -28,6 +34,7 @@ impl HardwareTask {
                     let FilterAttrs { cfgs, attrs, .. } = util::filter_attributes(item.attrs);
 
                     return Ok(HardwareTask {
+                        binding: device::binding(&args.binds, settings),
                         args,
                         cfgs,
                         attrs,
@@ -53,9 +60,10 @@ impl HardwareTask {
     pub(crate) fn parse_foreign(
         args: HardwareTaskArgs,
         item: ForeignItemFn,
+        settings: &Settings,
     ) -> parse::Result<Self> {
         let span = item.sig.ident.span();
-        let valid_signature = util::check_foreign_fn_signature(&item)
+        let valid_signature = util::check_foreign_fn_signature(&item, false)
             && item.sig.inputs.len() == 1
             && util::type_is_unit(&item.sig.output);
 
@@ -74,6 +82,7 @@ impl HardwareTask {
                     let FilterAttrs { cfgs, attrs, .. } = util::filter_attributes(item.attrs);
 
                     return Ok(HardwareTask {
+                        binding: device::binding(&args.binds, settings),
                         args,
                         cfgs,
                         attrs,
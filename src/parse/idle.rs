@@ -4,6 +4,7 @@ use syn::{parse, ItemFn};
 use crate::{
     ast::{Idle, IdleArgs},
     parse::util,
+    Settings,
 };
 
 impl IdleArgs {
@@ -13,13 +14,17 @@ impl IdleArgs {
 }
 
 impl Idle {
-    pub(crate) fn parse(args: IdleArgs, item: ItemFn) -> parse::Result<Self> {
-        let valid_signature = util::check_fn_signature(&item)
+    pub(crate) fn parse(args: IdleArgs, item: ItemFn, settings: &Settings) -> parse::Result<Self> {
+        util::check_async(&item.sig.asyncness, settings)?;
+
+        let valid_signature = util::check_fn_signature(&item, settings.parse_async_tasks)
             && item.sig.inputs.len() == 1
             && util::type_is_bottom(&item.sig.output);
 
         let name = item.sig.ident.to_string();
 
+        let is_async = item.sig.asyncness.is_some();
+
         if valid_signature {
             if let Some((context, Ok(rest))) = util::parse_inputs(item.sig.inputs, &name) {
                 if rest.is_empty() {
@@ -29,6 +34,7 @@ impl Idle {
                         context,
                         name: item.sig.ident,
                         stmts: item.block.stmts,
+                        is_async,
                     });
                 }
             }
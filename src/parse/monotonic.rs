@@ -5,17 +5,28 @@ use syn::{parse, spanned::Spanned, ItemType, Visibility};
 use crate::parse::util::FilterAttrs;
 use crate::{
     ast::{Monotonic, MonotonicArgs},
-    parse::util,
+    device,
+    parse::{util, Consts},
+    Settings,
 };
 
 impl MonotonicArgs {
-    pub(crate) fn parse(attr: Attribute) -> parse::Result<Self> {
-        crate::parse::monotonic_args(attr.path, attr.tokens)
+    pub(crate) fn parse(
+        attr: Attribute,
+        settings: &Settings,
+        consts: &Consts,
+    ) -> parse::Result<Self> {
+        crate::parse::monotonic_args(attr.path, attr.tokens, settings, consts)
     }
 }
 
 impl Monotonic {
-    pub(crate) fn parse(args: MonotonicArgs, item: &ItemType, span: Span) -> parse::Result<Self> {
+    pub(crate) fn parse(
+        args: MonotonicArgs,
+        item: &ItemType,
+        span: Span,
+        settings: &Settings,
+    ) -> parse::Result<Self> {
         if item.vis != Visibility::Inherited {
             return Err(parse::Error::new(
                 span,
@@ -36,6 +47,7 @@ impl Monotonic {
             cfgs,
             ident: item.ident.clone(),
             ty: item.ty.clone(),
+            binding: device::binding(&args.binds, settings),
             args,
         })
     }
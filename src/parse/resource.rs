@@ -23,13 +23,21 @@ impl SharedResource {
         } = util::filter_attributes(item.attrs.clone());
 
         let lock_free = util::extract_lock_free(&mut attrs)?;
+        let atomic = util::extract_atomic(&mut attrs)?;
+
+        if lock_free && atomic {
+            return Err(parse::Error::new(
+                span,
+                "a shared resource can't be both `#[lock_free]` and `#[atomic]`",
+            ));
+        }
 
         Ok(SharedResource {
             cfgs,
             attrs,
             docs,
             ty: Box::new(item.ty.clone()),
-            properties: SharedResourceProperties { lock_free },
+            properties: SharedResourceProperties { lock_free, atomic },
         })
     }
 }
@@ -4,17 +4,26 @@ use crate::parse::util::FilterAttrs;
 use crate::{
     ast::{SoftwareTask, SoftwareTaskArgs},
     parse::util,
+    Settings,
 };
 
 impl SoftwareTask {
-    pub(crate) fn parse(args: SoftwareTaskArgs, item: ItemFn) -> parse::Result<Self> {
-        let valid_signature =
-            util::check_fn_signature(&item) && util::type_is_unit(&item.sig.output);
+    pub(crate) fn parse(
+        args: SoftwareTaskArgs,
+        item: ItemFn,
+        settings: &Settings,
+    ) -> parse::Result<Self> {
+        util::check_async(&item.sig.asyncness, settings)?;
+
+        let valid_signature = util::check_fn_signature(&item, settings.parse_async_tasks)
+            && util::type_is_unit(&item.sig.output);
 
         let span = item.sig.ident.span();
 
         let name = item.sig.ident.to_string();
 
+        let is_async = item.sig.asyncness.is_some();
+
         if valid_signature {
             if let Some((context, Ok(inputs))) = util::parse_inputs(item.sig.inputs, &name) {
                 let FilterAttrs { cfgs, attrs, .. } = util::filter_attributes(item.attrs);
@@ -27,6 +36,7 @@ impl SoftwareTask {
                     inputs,
                     stmts: item.block.stmts,
                     is_extern: false,
+                    is_async,
                 });
             }
         }
@@ -45,14 +55,19 @@ impl SoftwareTask {
     pub(crate) fn parse_foreign(
         args: SoftwareTaskArgs,
         item: ForeignItemFn,
+        settings: &Settings,
     ) -> parse::Result<Self> {
-        let valid_signature =
-            util::check_foreign_fn_signature(&item) && util::type_is_unit(&item.sig.output);
+        util::check_async(&item.sig.asyncness, settings)?;
+
+        let valid_signature = util::check_foreign_fn_signature(&item, settings.parse_async_tasks)
+            && util::type_is_unit(&item.sig.output);
 
         let span = item.sig.ident.span();
 
         let name = item.sig.ident.to_string();
 
+        let is_async = item.sig.asyncness.is_some();
+
         if valid_signature {
             if let Some((context, Ok(inputs))) = util::parse_inputs(item.sig.inputs, &name) {
                 let FilterAttrs { cfgs, attrs, .. } = util::filter_attributes(item.attrs);
@@ -65,6 +80,7 @@ impl SoftwareTask {
                     inputs,
                     stmts: Vec::<Stmt>::new(),
                     is_extern: true,
+                    is_async,
                 });
             }
         }
@@ -1,15 +1,18 @@
+use core::time::Duration;
+
+use proc_macro2::TokenTree;
 use syn::{
     bracketed,
     parse::{self, ParseStream},
     punctuated::Punctuated,
     spanned::Spanned,
-    Abi, AttrStyle, Attribute, Expr, FnArg, ForeignItemFn, Ident, ItemFn, Pat, PatType, Path,
-    PathArguments, ReturnType, Token, Type, Visibility,
+    Abi, AttrStyle, Attribute, Expr, FnArg, ForeignItemFn, Ident, ItemFn, LitInt, Pat, PatType,
+    Path, PathArguments, ReturnType, Token, Type, Visibility,
 };
 
 use crate::{
-    ast::{Access, Local, LocalResources, SharedResources, TaskLocal},
-    Map,
+    ast::{Access, Local, LocalResources, SharedAccess, SharedResources, TaskLocal},
+    Map, Settings,
 };
 
 pub fn abi_is_rust(abi: &Abi) -> bool {
@@ -26,19 +29,30 @@ pub fn attr_eq(attr: &Attribute, name: &str) -> bool {
     }
 }
 
+/// rejects an `async` function unless `Settings::parse_async_tasks` is set
+pub fn check_async(asyncness: &Option<Token![async]>, settings: &Settings) -> parse::Result<()> {
+    match asyncness {
+        Some(asyncness) if !settings.parse_async_tasks => Err(parse::Error::new(
+            asyncness.span(),
+            "`async` tasks are not supported; enable `Settings::parse_async_tasks`",
+        )),
+        _ => Ok(()),
+    }
+}
+
 /// checks that a function signature
 ///
 /// - has no bounds (like where clauses)
-/// - is not `async`
+/// - is not `async` (unless `allow_async` is set)
 /// - is not `const`
 /// - is not `unsafe`
 /// - is not generic (has no type parameters)
 /// - is not variadic
 /// - uses the Rust ABI (and not e.g. "C")
-pub fn check_fn_signature(item: &ItemFn) -> bool {
+pub fn check_fn_signature(item: &ItemFn, allow_async: bool) -> bool {
     item.vis == Visibility::Inherited
         && item.sig.constness.is_none()
-        && item.sig.asyncness.is_none()
+        && (allow_async || item.sig.asyncness.is_none())
         && item.sig.abi.is_none()
         && item.sig.unsafety.is_none()
         && item.sig.generics.params.is_empty()
@@ -47,10 +61,10 @@ pub fn check_fn_signature(item: &ItemFn) -> bool {
 }
 
 #[allow(dead_code)]
-pub fn check_foreign_fn_signature(item: &ForeignItemFn) -> bool {
+pub fn check_foreign_fn_signature(item: &ForeignItemFn, allow_async: bool) -> bool {
     item.vis == Visibility::Inherited
         && item.sig.constness.is_none()
-        && item.sig.asyncness.is_none()
+        && (allow_async || item.sig.asyncness.is_none())
         && item.sig.abi.is_none()
         && item.sig.unsafety.is_none()
         && item.sig.generics.params.is_empty()
@@ -58,6 +72,94 @@ pub fn check_foreign_fn_signature(item: &ForeignItemFn) -> bool {
         && item.sig.variadic.is_none()
 }
 
+/// Parses a comma separated list of `ident = value` arguments
+///
+/// `f` is called with each argument name and must parse its value. An error doesn't stop the
+/// parsing: the rest of the offending argument is skipped and all errors are reported together
+pub fn parse_arguments(
+    content: ParseStream<'_>,
+    mut f: impl FnMut(Ident, ParseStream<'_>) -> parse::Result<()>,
+) -> parse::Result<()> {
+    let mut errors = vec![];
+
+    while !content.is_empty() {
+        let result = (|| {
+            // Parse identifier name
+            let ident: Ident = content.parse()?;
+            // Handle equal sign
+            let _: Token![=] = content.parse()?;
+
+            f(ident, content)?;
+
+            if !content.is_empty() {
+                // Handle comma: ,
+                let _: Token![,] = content.parse()?;
+            }
+
+            Ok(())
+        })();
+
+        if let Err(e) = result {
+            errors.push(e);
+            skip_argument(content);
+        }
+    }
+
+    crate::combine_errors(errors)
+}
+
+/// Skips tokens up to, and including, the next comma
+fn skip_argument(content: ParseStream<'_>) {
+    while !content.is_empty() {
+        if content.parse::<Token![,]>().is_ok() {
+            break;
+        }
+
+        let _ = content.parse::<TokenTree>();
+    }
+}
+
+/// Parses the value of a `core = N` argument
+pub fn parse_core(content: ParseStream<'_>) -> parse::Result<u8> {
+    let lit: LitInt = content.parse()?;
+
+    if !lit.suffix().is_empty() {
+        return Err(parse::Error::new(
+            lit.span(),
+            "this literal must be unsuffixed",
+        ));
+    }
+
+    lit.base10_parse::<u8>()
+        .map_err(|_| parse::Error::new(lit.span(), "this literal must be in the range 0...255"))
+}
+
+/// Parses a duration with a unit, e.g. `120us`; the units are `ns`, `us`, `ms` and `s`
+pub fn parse_duration(content: ParseStream<'_>) -> parse::Result<Duration> {
+    let lit: LitInt = content.parse()?;
+
+    let value = lit.base10_parse::<u64>()?;
+    let duration = match lit.suffix() {
+        "ns" => Duration::from_nanos(value),
+        "us" => Duration::from_micros(value),
+        "ms" => Duration::from_millis(value),
+        "s" => Duration::from_secs(value),
+        _ => return Err(parse::Error::new(
+            lit.span(),
+            "expected a duration with a unit, e.g. `120us`; the units are `ns`, `us`, `ms` and `s`",
+        )),
+    };
+
+    if duration.is_zero() {
+        return Err(parse::Error::new(
+            lit.span(),
+            "this duration must be greater than zero",
+        ));
+    }
+
+    Ok(duration)
+}
+
 pub struct FilterAttrs {
     pub cfgs: Vec<Attribute>,
     pub docs: Vec<Attribute>,
@@ -91,43 +193,92 @@ pub fn extract_lock_free(attrs: &mut Vec<Attribute>) -> parse::Result<bool> {
     }
 }
 
+pub fn extract_atomic(attrs: &mut Vec<Attribute>) -> parse::Result<bool> {
+    if let Some(pos) = attrs.iter().position(|attr| attr_eq(attr, "atomic")) {
+        attrs.remove(pos);
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
 pub fn parse_shared_resources(content: ParseStream<'_>) -> parse::Result<SharedResources> {
     let inner;
     bracketed!(inner in content);
 
     let mut resources = Map::new();
+    let mut errors = vec![];
     for e in inner.call(Punctuated::<Expr, Token![,]>::parse_terminated)? {
-        let err = Err(parse::Error::new(
-            e.span(),
-            "identifier appears more than once in list",
-        ));
-        let (access, path) = match e {
-            Expr::Path(e) => (Access::Exclusive, e.path),
+        let err = parse::Error::new(e.span(), "identifier appears more than once in list");
+        let (access, path, attrs) = match e {
+            Expr::Path(e) => (Access::Exclusive, e.path, e.attrs),
 
             Expr::Reference(ref r) if r.mutability.is_none() => match &*r.expr {
-                Expr::Path(e) => (Access::Shared, e.path.clone()),
+                // `&#[cfg(..)] x`
+                Expr::Path(e) if !e.attrs.is_empty() => {
+                    let error = match extract_entry_cfgs(e.attrs.clone()) {
+                        Ok(cfgs) => {
+                            parse::Error::new(cfgs[0].span(), "put the `#[cfg]` before the `&`")
+                        }
+                        Err(e) => e,
+                    };
+                    errors.push(error);
+                    continue;
+                }
 
-                _ => return err,
+                Expr::Path(e) => (Access::Shared, e.path.clone(), r.attrs.clone()),
+
+                _ => {
+                    errors.push(err);
+                    continue;
+                }
             },
 
-            _ => return err,
+            _ => {
+                errors.push(err);
+                continue;
+            }
         };
 
-        let ident = extract_resource_name_ident(path)?;
+        let (ident, cfgs) = match extract_resource_name_ident(path)
+            .and_then(|ident| Ok((ident, extract_entry_cfgs(attrs)?)))
+        {
+            Ok(entry) => entry,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
 
         if resources.contains_key(&ident) {
-            return Err(parse::Error::new(
+            errors.push(parse::Error::new(
                 ident.span(),
                 "resource appears more than once in list",
             ));
+            continue;
         }
 
-        resources.insert(ident, access);
+        resources.insert(ident, SharedAccess { access, cfgs });
     }
 
+    crate::combine_errors(errors)?;
+
     Ok(resources)
 }
 
+/// The `#[cfg]`s of a resource list entry; other attributes are rejected
+fn extract_entry_cfgs(attrs: Vec<Attribute>) -> parse::Result<Vec<Attribute>> {
+    let FilterAttrs { cfgs, docs, attrs } = filter_attributes(attrs);
+
+    match docs.iter().chain(&attrs).next() {
+        Some(attr) => Err(parse::Error::new(
+            attr.span(),
+            "only `#[cfg]` attributes are supported here",
+        )),
+        None => Ok(cfgs),
+    }
+}
+
 fn extract_resource_name_ident(path: Path) -> parse::Result<Ident> {
     if path.leading_colon.is_some()
         || path.segments.len() != 1
@@ -142,11 +293,23 @@ fn extract_resource_name_ident(path: Path) -> parse::Result<Ident> {
     }
 }
 
+/// Declared local resources must have an array, pointer, tuple or path type
+fn check_local_type(ty: &Type) -> parse::Result<()> {
+    match ty {
+        Type::Array(_) | Type::Path(_) | Type::Ptr(_) | Type::Tuple(_) => Ok(()),
+        _ => Err(parse::Error::new(
+            ty.span(),
+            "unsupported type, must be an array, tuple, pointer or type path",
+        )),
+    }
+}
+
 pub fn parse_local_resources(content: ParseStream<'_>) -> parse::Result<LocalResources> {
     let inner;
     bracketed!(inner in content);
 
     let mut resources = Map::new();
+    let mut errors = vec![];
 
     for e in inner.call(Punctuated::<Expr, Token![,]>::parse_terminated)? {
         let err = Err(parse::Error::new(
@@ -154,80 +317,74 @@ pub fn parse_local_resources(content: ParseStream<'_>) -> parse::Result<LocalRes
             "identifier appears more than once in list",
         ));
 
-        let (name, local) = match e {
-            // local = [IDENT],
-            Expr::Path(path) => {
-                if !path.attrs.is_empty() {
-                    return Err(parse::Error::new(
-                        path.span(),
-                        "attributes are not supported here",
-                    ));
-                }
+        // An error in one entry doesn't stop the parsing of the following ones
+        let entry = (|| -> parse::Result<_> {
+            Ok(match e {
+                // local = [IDENT],
+                Expr::Path(path) => {
+                    let cfgs = extract_entry_cfgs(path.attrs)?;
+                    let ident = extract_resource_name_ident(path.path)?;
 
-                let ident = extract_resource_name_ident(path.path)?;
-                // let (cfgs, attrs) = extract_cfgs(path.attrs);
+                    (ident, TaskLocal::External { cfgs })
+                }
 
-                (ident, TaskLocal::External)
-            }
+                // local = [IDENT: TYPE = EXPR]
+                Expr::Assign(e) => {
+                    let (name, ty, cfgs, attrs) = match *e.left {
+                        Expr::Type(t) => {
+                            // Extract name and attributes
+                            let (name, cfgs, attrs) = match *t.expr {
+                                Expr::Path(path) => {
+                                    let name = extract_resource_name_ident(path.path)?;
+                                    let FilterAttrs { cfgs, attrs, .. } =
+                                        filter_attributes(path.attrs);
+
+                                    (name, cfgs, attrs)
+                                }
+                                _ => return err,
+                            };
+
+                            let ty = t.ty;
+
+                            check_local_type(&ty)?;
+
+                            (name, ty, cfgs, attrs)
+                        }
+                        e => return Err(parse::Error::new(e.span(), "malformed, expected a type")),
+                    };
+
+                    let expr = e.right; // Expr
+
+                    (
+                        name,
+                        TaskLocal::Declared(Local {
+                            attrs,
+                            cfgs,
+                            ty,
+                            expr,
+                        }),
+                    )
+                }
 
-            // local = [IDENT: TYPE = EXPR]
-            Expr::Assign(e) => {
-                let (name, ty, cfgs, attrs) = match *e.left {
-                    Expr::Type(t) => {
-                        // Extract name and attributes
-                        let (name, cfgs, attrs) = match *t.expr {
-                            Expr::Path(path) => {
-                                let name = extract_resource_name_ident(path.path)?;
-                                let FilterAttrs { cfgs, attrs, .. } = filter_attributes(path.attrs);
-
-                                (name, cfgs, attrs)
-                            }
-                            _ => return err,
-                        };
-
-                        let ty = t.ty;
-
-                        // Error check
-                        match &*ty {
-                            Type::Array(_) => {}
-                            Type::Path(_) => {}
-                            Type::Ptr(_) => {}
-                            Type::Tuple(_) => {}
-                            _ => return Err(parse::Error::new(
-                                ty.span(),
-                                "unsupported type, must be an array, tuple, pointer or type path",
-                            )),
-                        };
-
-                        (name, ty, cfgs, attrs)
-                    }
-                    e => return Err(parse::Error::new(e.span(), "malformed, expected a type")),
-                };
-
-                let expr = e.right; // Expr
-
-                (
-                    name,
-                    TaskLocal::Declared(Local {
-                        attrs,
-                        cfgs,
-                        ty,
-                        expr,
-                    }),
-                )
-            }
+                expr => {
+                    return Err(parse::Error::new(
+                        expr.span(),
+                        "malformed, expected 'IDENT: TYPE = EXPR'",
+                    ))
+                }
+            })
+        })();
 
-            expr => {
-                return Err(parse::Error::new(
-                    expr.span(),
-                    "malformed, expected 'IDENT: TYPE = EXPR'",
-                ))
+        match entry {
+            Ok((name, local)) => {
+                resources.insert(name, local);
             }
-        };
-
-        resources.insert(name, local);
+            Err(e) => errors.push(e),
+        }
     }
 
+    crate::combine_errors(errors)?;
+
     Ok(resources)
 }
 
@@ -0,0 +1,378 @@
+use proc_macro2::{Literal, Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote, ToTokens};
+use syn::{Ident, Item, LitInt};
+
+use crate::{
+    ast::{Access, App, AppArgs, LocalResources, SharedResources, TaskLocal, Timing},
+    schedule, Map,
+};
+
+/// Prints the arguments of the `#[app]` attribute, e.g. `device = lm3s6965, dispatchers = [A]`
+///
+/// Arguments that have their default value are omitted
+impl ToTokens for AppArgs {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let mut args = vec![];
+
+        if let Some(device) = &self.device {
+            args.push(quote!(device = #device));
+        }
+
+        if !self.peripherals {
+            args.push(quote!(peripherals = false));
+        }
+
+        if self.cores != 1 {
+            let cores = Literal::u8_unsuffixed(self.cores);
+            args.push(quote!(cores = #cores));
+        }
+
+        if !self.extern_interrupts.is_empty() {
+            let interrupts = self.extern_interrupts.iter().map(|(name, interrupt)| {
+                let attrs = &interrupt.attrs;
+                quote!(#(#attrs)* #name)
+            });
+            args.push(quote!(dispatchers = [#(#interrupts),*]));
+        }
+
+        tokens.extend(quote!(#(#args),*));
+    }
+}
+
+/// Prints the application back as an `#[app(..)] mod name { .. }` item
+///
+/// Parsing the output (with `Settings::parse_binds` if there are hardware tasks) gives back an
+/// equivalent `App`. The items are printed in a fixed order: user imports, the resource structs,
+/// monotonics, `#[init]`, `#[idle]`, tasks, `extern` tasks and then the rest of the user code.
+/// Task arguments that have their default value (e.g. `priority = 1`, `core = 0`) are omitted.
+impl ToTokens for App {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        tokens.extend(self.print(false));
+    }
+}
+
+impl App {
+    /// Prints the application like its `ToTokens` implementation does but with the fields of the
+    /// `#[shared]` and `#[local]` structs and the `shared = [..]` / `local = [..]` lists of every
+    /// context sorted by name
+    ///
+    /// The output is a canonical form of the application. The `dispatchers` are not sorted as
+    /// their order decides which interrupt dispatches which priority level
+    pub fn to_canonical_tokens(&self) -> TokenStream2 {
+        self.print(true)
+    }
+
+    fn print(&self, canonical: bool) -> TokenStream2 {
+        let args = &self.args;
+        let name = &self.name;
+        let user_imports = &self.user_imports;
+
+        // Resources
+        let shared_struct = &self.init.user_shared_struct;
+        let shared_fields =
+            entries(&self.shared_resources, canonical)
+                .into_iter()
+                .map(|(name, res)| {
+                    let (cfgs, docs, attrs, ty) = (&res.cfgs, &res.docs, &res.attrs, &res.ty);
+                    let lock_free = if res.properties.lock_free {
+                        Some(quote!(#[lock_free]))
+                    } else if res.properties.atomic {
+                        Some(quote!(#[atomic]))
+                    } else {
+                        None
+                    };
+
+                    quote!(#(#cfgs)* #(#docs)* #(#attrs)* #lock_free #name: #ty)
+                });
+
+        let local_struct = &self.init.user_local_struct;
+        let local_fields =
+            entries(&self.local_resources, canonical)
+                .into_iter()
+                .map(|(name, res)| {
+                    let (cfgs, docs, attrs, ty) = (&res.cfgs, &res.docs, &res.attrs, &res.ty);
+
+                    quote!(#(#cfgs)* #(#docs)* #(#attrs)* #name: #ty)
+                });
+
+        let monotonics = self.monotonics.values().map(|monotonic| {
+            let (cfgs, ident, ty) = (&monotonic.cfgs, &monotonic.ident, &monotonic.ty);
+
+            let binds = &monotonic.args.binds;
+            let mut args = vec![quote!(binds = #binds)];
+            if let Some(priority) = monotonic.args.priority {
+                let priority = Literal::u8_unsuffixed(priority);
+                args.push(quote!(priority = #priority));
+            }
+            args.extend(core(monotonic.args.core));
+            if monotonic.args.default {
+                args.push(quote!(default = true));
+            }
+
+            quote!(#(#cfgs)* #[monotonic(#(#args),*)] type #ident = #ty;)
+        });
+
+        // Contexts
+        let init = {
+            let (attrs, name, context, stmts) = (
+                &self.init.attrs,
+                &self.init.name,
+                &self.init.context,
+                &self.init.stmts,
+            );
+            let args = core(self.init.args.core)
+                .into_iter()
+                .chain(local_resources(canonical, &self.init.args.local_resources))
+                .collect();
+            let attr = attribute("init", args);
+
+            quote!(
+                #(#attrs)*
+                #attr
+                fn #name(#context: #name::Context) -> (#shared_struct, #local_struct, #name::Monotonics) {
+                    #(#stmts)*
+                }
+            )
+        };
+
+        let idle = self.idle.as_ref().map(|idle| {
+            let (attrs, name, context, stmts) =
+                (&idle.attrs, &idle.name, &idle.context, &idle.stmts);
+            let asyncness = if idle.is_async {
+                Some(quote!(async))
+            } else {
+                None
+            };
+            let args = core(idle.args.core)
+                .into_iter()
+                .chain(shared_resources(canonical, &idle.args.shared_resources))
+                .chain(local_resources(canonical, &idle.args.local_resources))
+                .collect();
+            let attr = attribute("idle", args);
+
+            quote!(
+                #(#attrs)*
+                #attr
+                #asyncness fn #name(#context: #name::Context) -> ! {
+                    #(#stmts)*
+                }
+            )
+        });
+
+        let mut tasks = vec![];
+        let mut extern_tasks = vec![];
+
+        for (name, task) in &self.hardware_tasks {
+            let (cfgs, attrs, context) = (&task.cfgs, &task.attrs, &task.context);
+
+            let binds = &task.args.binds;
+            let args = Some(quote!(binds = #binds))
+                .into_iter()
+                .chain(priority(task.args.priority))
+                .chain(threshold(task.args.threshold))
+                .chain(core(task.args.core))
+                .chain(timing(&task.args.timing))
+                .chain(shared_resources(canonical, &task.args.shared_resources))
+                .chain(local_resources(canonical, &task.args.local_resources))
+                .collect();
+            let attr = attribute("task", args);
+
+            let signature = quote!(fn #name(#context: #name::Context));
+
+            if task.is_extern {
+                extern_tasks.push(quote!(#attr #signature;));
+            } else {
+                let stmts = &task.stmts;
+                tasks.push(quote!(
+                    #(#cfgs)*
+                    #(#attrs)*
+                    #attr
+                    #signature {
+                        #(#stmts)*
+                    }
+                ));
+            }
+        }
+
+        for (name, task) in &self.software_tasks {
+            let (cfgs, attrs, context, inputs) =
+                (&task.cfgs, &task.attrs, &task.context, &task.inputs);
+
+            let capacity = if task.args.capacity == 1 {
+                None
+            } else {
+                let capacity = Literal::u8_unsuffixed(task.args.capacity);
+                Some(quote!(capacity = #capacity))
+            };
+            let args = capacity
+                .into_iter()
+                .chain(priority(task.args.priority))
+                .chain(threshold(task.args.threshold))
+                .chain(core(task.args.core))
+                .chain(timing(&task.args.timing))
+                .chain(shared_resources(canonical, &task.args.shared_resources))
+                .chain(local_resources(canonical, &task.args.local_resources))
+                .collect();
+            let attr = attribute("task", args);
+
+            let asyncness = if task.is_async {
+                Some(quote!(async))
+            } else {
+                None
+            };
+            let signature = quote!(#asyncness fn #name(#context: #name::Context #(, #inputs)*));
+
+            if task.is_extern {
+                extern_tasks.push(quote!(#attr #signature;));
+            } else {
+                let stmts = &task.stmts;
+                tasks.push(quote!(
+                    #(#cfgs)*
+                    #(#attrs)*
+                    #attr
+                    #signature {
+                        #(#stmts)*
+                    }
+                ));
+            }
+        }
+
+        let extern_block = if extern_tasks.is_empty() {
+            None
+        } else {
+            Some(quote!(extern "Rust" { #(#extern_tasks)* }))
+        };
+
+        // The monotonic type aliases are also kept in the user code, they are printed above
+        let user_code = self.user_code.iter().filter(|item| match item {
+            Item::Type(item) => !self.monotonics.contains_key(&item.ident),
+            _ => true,
+        });
+
+        quote!(
+            #[app(#args)]
+            mod #name {
+                #(#user_imports)*
+
+                #[shared]
+                struct #shared_struct {
+                    #(#shared_fields,)*
+                }
+
+                #[local]
+                struct #local_struct {
+                    #(#local_fields,)*
+                }
+
+                #(#monotonics)*
+
+                #init
+
+                #idle
+
+                #(#tasks)*
+
+                #extern_block
+
+                #(#user_code)*
+            }
+        )
+    }
+}
+
+/// The entries of `map`, sorted by name if `canonical`
+fn entries<T>(map: &Map<T>, canonical: bool) -> Vec<(&Ident, &T)> {
+    let mut entries = map.iter().collect::<Vec<_>>();
+    if canonical {
+        entries.sort_by_key(|(name, _)| *name);
+    }
+    entries
+}
+
+/// `#[name]` or `#[name(args)]`
+fn attribute(name: &str, args: Vec<TokenStream2>) -> TokenStream2 {
+    let name = format_ident!("{}", name);
+
+    if args.is_empty() {
+        quote!(#[#name])
+    } else {
+        quote!(#[#name(#(#args),*)])
+    }
+}
+
+fn priority(priority: u8) -> Option<TokenStream2> {
+    if priority == 1 {
+        None
+    } else {
+        let priority = Literal::u8_unsuffixed(priority);
+        Some(quote!(priority = #priority))
+    }
+}
+
+fn threshold(threshold: Option<u8>) -> Option<TokenStream2> {
+    threshold.map(|threshold| {
+        let threshold = Literal::u8_unsuffixed(threshold);
+        quote!(threshold = #threshold)
+    })
+}
+
+fn core(core: u8) -> Option<TokenStream2> {
+    if core == 0 {
+        None
+    } else {
+        let core = Literal::u8_unsuffixed(core);
+        Some(quote!(core = #core))
+    }
+}
+
+fn timing(timing: &Timing) -> Vec<TokenStream2> {
+    [
+        ("wcet", timing.wcet),
+        ("period", timing.period),
+        ("deadline", timing.deadline),
+    ]
+    .iter()
+    .filter_map(|(name, duration)| {
+        let name = format_ident!("{}", name);
+        let duration = LitInt::new(&schedule::duration((*duration)?), Span::call_site());
+        Some(quote!(#name = #duration))
+    })
+    .collect()
+}
+
+fn shared_resources(canonical: bool, resources: &SharedResources) -> Option<TokenStream2> {
+    if resources.is_empty() {
+        return None;
+    }
+
+    let resources = entries(resources, canonical)
+        .into_iter()
+        .map(|(name, access)| {
+            let cfgs = &access.cfgs;
+            match access.access {
+                Access::Exclusive => quote!(#(#cfgs)* #name),
+                Access::Shared => quote!(#(#cfgs)* &#name),
+            }
+        });
+
+    Some(quote!(shared = [#(#resources),*]))
+}
+
+fn local_resources(canonical: bool, resources: &LocalResources) -> Option<TokenStream2> {
+    if resources.is_empty() {
+        return None;
+    }
+
+    let resources = entries(resources, canonical)
+        .into_iter()
+        .map(|(name, local)| match local {
+            TaskLocal::External { cfgs } => quote!(#(#cfgs)* #name),
+            TaskLocal::Declared(local) => {
+                let (attrs, cfgs, ty, expr) = (&local.attrs, &local.cfgs, &local.ty, &local.expr);
+
+                quote!(#(#cfgs)* #(#attrs)* #name: #ty = #expr)
+            }
+        });
+
+    Some(quote!(local = [#(#resources),*]))
+}
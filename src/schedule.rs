@@ -0,0 +1,231 @@
+//! Response-time analysis
+//!
+//! Classic fixed-priority response-time analysis of the tasks that have `wcet` and `period` /
+//! `deadline` annotations. Under the Stack Resource Policy a task can be blocked at most once, by
+//! a single lower priority task that holds a resource whose (read) ceiling is at least the
+//! priority of the task.
+//!
+//! The analysis is conservative: critical sections are assumed to last for the whole `wcet` of
+//! the blocking task, tasks of the same priority are counted as interference and the overhead
+//! of the dispatchers and of the monotonic handlers is not accounted for. Each core is analyzed
+//! on its own, and tasks under mutually exclusive `#[cfg]`s never block or preempt each other.
+
+use core::time::Duration;
+
+use syn::Ident;
+
+use crate::{
+    analyze::{Analysis, Core, Ownership, Priority},
+    ast::{App, SharedResources, Timing},
+    cfg::Cfg,
+    Map,
+};
+
+/// Worst-case response times, keyed by task; the variants of a task share the worst one
+pub type ResponseTimes = Map<ResponseTime>;
+
+/// The result of the response-time analysis of a task
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ResponseTime {
+    /// Worst-case execution time of the task
+    pub wcet: Duration,
+
+    /// Deadline of the task, its period unless a `deadline` is given
+    pub deadline: Duration,
+
+    /// Longest time the task can be blocked by a lower priority task that holds a resource
+    pub blocking: Duration,
+
+    /// Worst-case response time; `None` if it exceeds the deadline or can't be computed
+    pub response_time: Option<Duration>,
+
+    /// The tasks that can block or preempt this one but lack the `wcet` (or `period`)
+    /// annotation needed to compute the response time
+    pub missing_timing: Vec<Ident>,
+}
+
+impl ResponseTime {
+    /// Whether the task provably meets its deadline
+    pub fn is_schedulable(&self) -> bool {
+        self.response_time.is_some()
+    }
+}
+
+struct Task<'a> {
+    name: &'a Ident,
+    core: Core,
+    priority: Priority,
+    threshold: Priority,
+    timing: &'a Timing,
+    shared_resources: &'a SharedResources,
+    cfg: Cfg,
+}
+
+pub(crate) fn app(app: &App, analysis: &Analysis) -> ResponseTimes {
+    let tasks = app
+        .hardware_tasks
+        .iter()
+        .map(|(name, task)| Task {
+            name,
+            core: task.args.core,
+            priority: task.args.priority,
+            threshold: task.args.threshold(),
+            timing: &task.args.timing,
+            shared_resources: &task.args.shared_resources,
+            cfg: Cfg::from_attrs(&task.cfgs),
+        })
+        .chain(app.software_tasks.iter().map(|(name, task)| Task {
+            name,
+            core: task.args.core,
+            priority: task.args.priority,
+            threshold: task.args.threshold(),
+            timing: &task.args.timing,
+            shared_resources: &task.args.shared_resources,
+            cfg: Cfg::from_attrs(&task.cfgs),
+        }))
+        .collect::<Vec<_>>();
+
+    let mut response_times = ResponseTimes::new();
+    for task in &tasks {
+        let (wcet, deadline) = match (task.timing.wcet, task.timing.deadline()) {
+            (Some(wcet), Some(deadline)) => (wcet, deadline),
+            _ => continue,
+        };
+
+        let mut missing_timing = vec![];
+
+        // Lower priority tasks that run above our priority, because of their preemption threshold,
+        // or that can hold a resource locked above our priority
+        let blockers = tasks.iter().filter(|other| {
+            other.core == task.core
+                && other.priority < task.priority
+                && other.cfg.overlaps(&task.cfg)
+                && (other.threshold >= task.priority
+                    || other.shared_resources.iter().any(|(name, access)| {
+                        match analysis.ownerships.get(name) {
+                            Some(
+                                ownership @ Ownership::Contended {
+                                    ceiling,
+                                    read_ceiling,
+                                },
+                            ) => {
+                                let ceiling = if access.access.is_shared() {
+                                    *read_ceiling
+                                } else {
+                                    *ceiling
+                                };
+
+                                ownership.needs_lock(other.threshold, access.access)
+                                    && ceiling >= task.priority
+                            }
+                            _ => false,
+                        }
+                    }))
+        });
+
+        let mut blocking = Duration::ZERO;
+        for blocker in blockers {
+            match blocker.timing.wcet {
+                Some(wcet) => blocking = blocking.max(wcet),
+                None => missing_timing.push(blocker.name.clone()),
+            }
+        }
+
+        // Tasks that can run before us once we are released
+        let mut interference = vec![];
+        for other in &tasks {
+            if other.name == task.name
+                || other.core != task.core
+                || other.priority < task.priority
+                || !other.cfg.overlaps(&task.cfg)
+            {
+                continue;
+            }
+
+            match (other.timing.wcet, other.timing.period) {
+                (Some(wcet), Some(period)) => interference.push((wcet, period)),
+                _ => missing_timing.push(other.name.clone()),
+            }
+        }
+
+        let response_time = if missing_timing.is_empty() {
+            response_time(wcet + blocking, &interference, deadline)
+        } else {
+            None
+        };
+
+        let worst = match response_times.get(task.name) {
+            Some(variant) => match (variant.response_time, response_time) {
+                (Some(variant), Some(response_time)) => response_time > variant,
+                (Some(_), None) => true,
+                (None, _) => false,
+            },
+            None => true,
+        };
+        if !worst {
+            continue;
+        }
+
+        response_times.insert(
+            task.name.clone(),
+            ResponseTime {
+                wcet,
+                deadline,
+                blocking,
+                response_time,
+                missing_timing,
+            },
+        );
+    }
+
+    response_times
+}
+
+/// Solves `R = base + sum(ceil(R / period) * wcet)` by fixed-point iteration, `None` if `R`
+/// exceeds the deadline
+fn response_time(
+    base: Duration,
+    interference: &[(Duration, Duration)],
+    deadline: Duration,
+) -> Option<Duration> {
+    let (base, deadline) = (base.as_nanos(), deadline.as_nanos());
+
+    let mut response_time = base;
+    loop {
+        if response_time > deadline {
+            return None;
+        }
+
+        let next = base
+            + interference
+                .iter()
+                .map(|(wcet, period)| {
+                    let period = period.as_nanos();
+                    let releases = (response_time + period - 1) / period;
+                    releases * wcet.as_nanos()
+                })
+                .sum::<u128>();
+
+        if next == response_time {
+            return Some(Duration::from_nanos(response_time as u64));
+        }
+
+        response_time = next;
+    }
+}
+
+/// Formats `duration` like the task arguments, with the largest unit that represents it exactly:
+/// `1ms`, `120us`
+pub(crate) fn duration(duration: Duration) -> String {
+    let nanos = duration.as_nanos();
+
+    if nanos % 1_000_000_000 == 0 {
+        format!("{}s", nanos / 1_000_000_000)
+    } else if nanos % 1_000_000 == 0 {
+        format!("{}ms", nanos / 1_000_000)
+    } else if nanos % 1_000 == 0 {
+        format!("{}us", nanos / 1_000)
+    } else {
+        format!("{}ns", nanos)
+    }
+}
@@ -0,0 +1,763 @@
+//! Discrete-event simulation of the scheduling of an application
+//!
+//! The simulator runs a [`Model`] of the tasks (when they are released and what their bodies do)
+//! under the Stack Resource Policy: a task starts, or preempts the running one, only if its
+//! priority is higher than both the priority of the running task and the ceilings of the
+//! resources locked on its core. Software tasks are queued in their channel, in FIFO order, and a
+//! release that finds the task's `capacity` exhausted is dropped; so is the release of a hardware
+//! task whose interrupt is already pending.
+//!
+//! The simulation is in zero-overhead time: dispatchers, interrupt entry and the monotonic
+//! handlers take no time. Cores only interact through `spawn`s; locks on resources shared between
+//! cores don't block the other cores.
+
+use core::{cmp, fmt, time::Duration};
+use std::collections::BTreeMap;
+
+use syn::Ident;
+
+use crate::{
+    analyze::{Analysis, Core, Ownership, Priority},
+    ast::{Access, App, SharedResources},
+    schedule, Map, Set,
+};
+
+/// The model of the tasks, keyed by task
+///
+/// Tasks that are not in the model are never released, and run for no time when spawned
+pub type Model = Map<TaskModel>;
+
+/// The model of a task
+#[derive(Clone, Debug, Default)]
+pub struct TaskModel {
+    /// When the task is released, besides the `spawn`s of other tasks
+    pub arrival: Arrival,
+
+    /// What the body of the task does, in order
+    pub steps: Vec<Step>,
+}
+
+/// When a task is released
+#[derive(Clone, Debug)]
+pub enum Arrival {
+    /// Only by the `spawn`s of other tasks
+    Spawned,
+
+    /// Every `period`, starting at `offset`
+    Periodic {
+        /// Time of the first release
+        offset: Duration,
+
+        /// Time between two releases
+        period: Duration,
+    },
+
+    /// At these times
+    At(Vec<Duration>),
+}
+
+impl Default for Arrival {
+    fn default() -> Self {
+        Arrival::Spawned
+    }
+}
+
+/// A step of the body of a task
+#[derive(Clone, Debug)]
+pub enum Step {
+    /// Executes for this long
+    Run(Duration),
+
+    /// Locks a shared resource
+    Lock(Ident),
+
+    /// Unlocks the shared resource locked last
+    Unlock(Ident),
+
+    /// Spawns a software task
+    Spawn(Ident),
+}
+
+/// An error in the model
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The model has an entry for a task that doesn't exist
+    UnknownTask(Ident),
+
+    /// A task locks a resource that's not in its `shared = [..]` list
+    NotShared {
+        /// The task
+        task: Ident,
+
+        /// The resource
+        resource: Ident,
+    },
+
+    /// A task unlocks a resource that's not the one it locked last, or finishes with a resource
+    /// locked
+    UnbalancedLock {
+        /// The task
+        task: Ident,
+
+        /// The resource
+        resource: Ident,
+    },
+
+    /// A task spawns something that's not a software task
+    NotSoftwareTask {
+        /// The task
+        task: Ident,
+
+        /// What it spawns
+        spawnee: Ident,
+    },
+
+    /// The model names a task that has variants under mutually exclusive `#[cfg]`s; one of them
+    /// has to be selected with `Settings::cfg`
+    AmbiguousTask(Ident),
+
+    /// Tasks that spawn each other in a cycle without running for any time, which would keep the
+    /// simulated time from advancing; the cycle starts and ends with the same task
+    InstantSpawnCycle(Vec<Ident>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnknownTask(task) => write!(f, "`{}` is not a task", task),
+            Error::NotShared { task, resource } => write!(
+                f,
+                "`{}` locks `{}` but it's not in its `shared` resources",
+                task, resource
+            ),
+            Error::UnbalancedLock { task, resource } => write!(
+                f,
+                "`{}` doesn't unlock `{}` in the reverse order of the locks",
+                task, resource
+            ),
+            Error::NotSoftwareTask { task, spawnee } => {
+                write!(
+                    f,
+                    "`{}` spawns `{}` which is not a software task",
+                    task, spawnee
+                )
+            }
+            Error::AmbiguousTask(task) => write!(
+                f,
+                "`{}` has variants under mutually exclusive `#[cfg]`s, select one with \
+                 `Settings::cfg`",
+                task
+            ),
+            Error::InstantSpawnCycle(tasks) => write!(
+                f,
+                "{} spawn each other without running for any time",
+                tasks
+                    .iter()
+                    .map(|task| format!("`{}`", task))
+                    .collect::<Vec<_>>()
+                    .join(" -> ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// An event of the simulation
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Event {
+    /// When it happened
+    pub time: Duration,
+
+    /// The core of the task
+    pub core: Core,
+
+    /// The task
+    pub task: Ident,
+
+    /// What happened
+    pub kind: EventKind,
+}
+
+/// What happened to a task
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EventKind {
+    /// The task was released
+    Release,
+
+    /// The release was dropped: the task's `capacity` is exhausted, or its interrupt is already
+    /// pending
+    Dropped,
+
+    /// The task started
+    Start,
+
+    /// The task was preempted by a higher priority task
+    Preempt,
+
+    /// The task resumed after a preemption
+    Resume,
+
+    /// The task locked a resource
+    Lock(Ident),
+
+    /// The task unlocked a resource
+    Unlock(Ident),
+
+    /// The task finished
+    Finish,
+
+    /// The task finished after its deadline (`deadline`, or `period`, of `#[task]`)
+    DeadlineMiss,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>10} core {} {:<20} ",
+            schedule::duration(self.time),
+            self.core,
+            self.task.to_string()
+        )?;
+
+        match &self.kind {
+            EventKind::Release => f.write_str("release"),
+            EventKind::Dropped => f.write_str("dropped"),
+            EventKind::Start => f.write_str("start"),
+            EventKind::Preempt => f.write_str("preempt"),
+            EventKind::Resume => f.write_str("resume"),
+            EventKind::Lock(resource) => write!(f, "lock {}", resource),
+            EventKind::Unlock(resource) => write!(f, "unlock {}", resource),
+            EventKind::Finish => f.write_str("finish"),
+            EventKind::DeadlineMiss => f.write_str("deadline miss"),
+        }
+    }
+}
+
+/// The events of a simulation, in chronological order
+#[derive(Clone, Debug, Default)]
+pub struct Trace {
+    /// The events
+    pub events: Vec<Event>,
+}
+
+impl Trace {
+    /// The releases that were dropped
+    pub fn drops(&self) -> impl Iterator<Item = &Event> {
+        self.events
+            .iter()
+            .filter(|event| event.kind == EventKind::Dropped)
+    }
+
+    /// The jobs that finished after their deadline
+    pub fn deadline_misses(&self) -> impl Iterator<Item = &Event> {
+        self.events
+            .iter()
+            .filter(|event| event.kind == EventKind::DeadlineMiss)
+    }
+}
+
+/// Renders the trace one event per line
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for event in &self.events {
+            writeln!(f, "{}", event)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Simulates `model` from time 0 to `until`
+pub fn simulate(
+    app: &App,
+    analysis: &Analysis,
+    model: &Model,
+    until: Duration,
+) -> Result<Trace, Error> {
+    let (tasks, variants) = tasks(app);
+    validate(&tasks, &variants, model)?;
+
+    // External releases, in chronological order
+    let mut arrivals = BTreeMap::<Duration, Vec<&Ident>>::new();
+    for (name, task) in model {
+        match &task.arrival {
+            Arrival::Spawned => {}
+            Arrival::Periodic { offset, period } => {
+                let mut time = *offset;
+                while time <= until {
+                    arrivals.entry(time).or_default().push(name);
+
+                    if period.is_zero() {
+                        break;
+                    }
+                    time += *period;
+                }
+            }
+            Arrival::At(times) => {
+                for time in times.iter().filter(|time| **time <= until) {
+                    arrivals.entry(*time).or_default().push(name);
+                }
+            }
+        }
+    }
+
+    let mut simulator = Simulator {
+        analysis,
+        model,
+        tasks,
+        time: Duration::ZERO,
+        sequence: 0,
+        ready: vec![],
+        pending: Map::new(),
+        stacks: BTreeMap::new(),
+        trace: Trace::default(),
+    };
+
+    loop {
+        if let Some(names) = arrivals.remove(&simulator.time) {
+            for name in names {
+                simulator.release(name);
+            }
+        }
+        simulator.settle();
+
+        // The next time something happens: a release or the end of a `Run` step
+        let next_arrival = arrivals.keys().next().copied();
+        let next_completion = simulator
+            .stacks
+            .values()
+            .filter_map(|stack| stack.last())
+            .map(|job| simulator.time + job.remaining)
+            .min();
+
+        let next = match (next_arrival, next_completion) {
+            (Some(a), Some(b)) => a.min(b),
+            (Some(time), None) | (None, Some(time)) => time,
+            (None, None) => break,
+        };
+
+        if next > until {
+            break;
+        }
+
+        let elapsed = next - simulator.time;
+        for job in simulator
+            .stacks
+            .values_mut()
+            .filter_map(|stack| stack.last_mut())
+        {
+            job.remaining -= elapsed;
+        }
+        simulator.time = next;
+    }
+
+    Ok(simulator.trace)
+}
+
+struct Task<'a> {
+    core: Core,
+    priority: Priority,
+    threshold: Priority,
+    /// `None` for hardware tasks
+    capacity: Option<u8>,
+    deadline: Option<Duration>,
+    shared_resources: &'a SharedResources,
+}
+
+/// The tasks by name, and the names of the tasks that have variants, which a model can't tell apart
+fn tasks(app: &App) -> (Map<Task<'_>>, Set<Ident>) {
+    let all = app
+        .hardware_tasks
+        .iter()
+        .map(|(name, task)| {
+            (
+                name.clone(),
+                Task {
+                    core: task.args.core,
+                    priority: task.args.priority,
+                    threshold: task.args.threshold(),
+                    capacity: None,
+                    deadline: task.args.timing.deadline(),
+                    shared_resources: &task.args.shared_resources,
+                },
+            )
+        })
+        .chain(app.software_tasks.iter().map(|(name, task)| {
+            (
+                name.clone(),
+                Task {
+                    core: task.args.core,
+                    priority: task.args.priority,
+                    threshold: task.args.threshold(),
+                    capacity: Some(task.args.capacity),
+                    deadline: task.args.timing.deadline(),
+                    shared_resources: &task.args.shared_resources,
+                },
+            )
+        }));
+
+    let mut tasks = Map::new();
+    let mut variants = Set::new();
+    for (name, task) in all {
+        if tasks.insert(name.clone(), task).is_some() {
+            variants.insert(name);
+        }
+    }
+
+    (tasks, variants)
+}
+
+fn validate(tasks: &Map<Task<'_>>, variants: &Set<Ident>, model: &Model) -> Result<(), Error> {
+    for (name, task_model) in model {
+        if variants.contains(name) {
+            return Err(Error::AmbiguousTask(name.clone()));
+        }
+
+        let task = tasks
+            .get(name)
+            .ok_or_else(|| Error::UnknownTask(name.clone()))?;
+
+        let mut locks = vec![];
+        for step in &task_model.steps {
+            match step {
+                Step::Run(_) => {}
+
+                Step::Lock(resource) => {
+                    if !task.shared_resources.contains_key(resource) {
+                        return Err(Error::NotShared {
+                            task: name.clone(),
+                            resource: resource.clone(),
+                        });
+                    }
+
+                    locks.push(resource);
+                }
+
+                Step::Unlock(resource) => {
+                    if locks.pop() != Some(resource) {
+                        return Err(Error::UnbalancedLock {
+                            task: name.clone(),
+                            resource: resource.clone(),
+                        });
+                    }
+                }
+
+                Step::Spawn(spawnee) => {
+                    if variants.contains(spawnee) {
+                        return Err(Error::AmbiguousTask(spawnee.clone()));
+                    }
+
+                    if !matches!(tasks.get(spawnee), Some(task) if task.capacity.is_some()) {
+                        return Err(Error::NotSoftwareTask {
+                            task: name.clone(),
+                            spawnee: spawnee.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        if let Some(resource) = locks.pop() {
+            return Err(Error::UnbalancedLock {
+                task: name.clone(),
+                resource: resource.clone(),
+            });
+        }
+    }
+
+    let mut done = Set::new();
+    for name in model.keys() {
+        if let Some(cycle) = instant_cycle(model, name, &mut vec![], &mut done) {
+            return Err(Error::InstantSpawnCycle(cycle));
+        }
+    }
+
+    Ok(())
+}
+
+/// The tasks that `task` spawns if it doesn't run for any time
+fn instant_spawns<'a>(model: &'a Model, task: &Ident) -> impl Iterator<Item = &'a Ident> {
+    model
+        .get(task)
+        .filter(|task_model| {
+            task_model
+                .steps
+                .iter()
+                .all(|step| !matches!(step, Step::Run(duration) if !duration.is_zero()))
+        })
+        .into_iter()
+        .flat_map(|task_model| {
+            task_model.steps.iter().filter_map(|step| match step {
+                Step::Spawn(spawnee) => Some(spawnee),
+                _ => None,
+            })
+        })
+}
+
+/// A cycle of `instant_spawns` reachable from `task`; `path` is the chain of spawns that led to
+/// `task` and `done` the tasks already known to reach no cycle
+fn instant_cycle(
+    model: &Model,
+    task: &Ident,
+    path: &mut Vec<Ident>,
+    done: &mut Set<Ident>,
+) -> Option<Vec<Ident>> {
+    if let Some(start) = path.iter().position(|spawner| spawner == task) {
+        let mut cycle = path[start..].to_vec();
+        cycle.push(task.clone());
+        return Some(cycle);
+    }
+
+    if done.contains(task) {
+        return None;
+    }
+
+    path.push(task.clone());
+    for spawnee in instant_spawns(model, task) {
+        if let Some(cycle) = instant_cycle(model, spawnee, path, done) {
+            return Some(cycle);
+        }
+    }
+    path.pop();
+    done.insert(task.clone());
+
+    None
+}
+
+/// A released instance of a task
+struct Job<'a> {
+    task: &'a Ident,
+    priority: Priority,
+    /// The priority the job runs at once started
+    threshold: Priority,
+    /// Release order, for the FIFO order within a priority level
+    sequence: u64,
+    deadline: Option<Duration>,
+    steps: &'a [Step],
+    /// What's left of the current `Run` step
+    remaining: Duration,
+    /// The ceilings of the resources this job holds
+    ceilings: Vec<Priority>,
+    preempted: bool,
+}
+
+struct Simulator<'a> {
+    analysis: &'a Analysis,
+    model: &'a Model,
+    tasks: Map<Task<'a>>,
+    time: Duration,
+    sequence: u64,
+    /// Released jobs that haven't started yet
+    ready: Vec<(Core, Job<'a>)>,
+    /// Number of released jobs, that haven't started yet, of each task
+    pending: Map<u8>,
+    /// The started jobs of each core, the running one last
+    stacks: BTreeMap<Core, Vec<Job<'a>>>,
+    trace: Trace,
+}
+
+impl<'a> Simulator<'a> {
+    fn event(&mut self, core: Core, task: &Ident, kind: EventKind) {
+        self.trace.events.push(Event {
+            time: self.time,
+            core,
+            task: task.clone(),
+            kind,
+        });
+    }
+
+    fn release(&mut self, name: &'a Ident) {
+        let task = &self.tasks[name];
+        let (core, priority, threshold, capacity, deadline) = (
+            task.core,
+            task.priority,
+            task.threshold,
+            task.capacity,
+            task.deadline,
+        );
+
+        // One pending interrupt for hardware tasks, `capacity` messages for software tasks
+        let pending = self.pending.get(name).copied().unwrap_or(0);
+        if pending >= capacity.unwrap_or(1) {
+            self.event(core, name, EventKind::Dropped);
+            return;
+        }
+
+        self.event(core, name, EventKind::Release);
+        *self.pending.entry(name.clone()).or_default() += 1;
+
+        let steps = self
+            .model
+            .get(name)
+            .map(|task| &task.steps[..])
+            .unwrap_or(&[]);
+        let job = Job {
+            task: name,
+            priority,
+            threshold,
+            sequence: self.sequence,
+            deadline: deadline.map(|deadline| self.time + deadline),
+            steps,
+            remaining: first_run(steps),
+            ceilings: vec![],
+            preempted: false,
+        };
+        self.sequence += 1;
+
+        self.ready.push((core, job));
+    }
+
+    /// Processes everything that happens at the current time
+    fn settle(&mut self) {
+        loop {
+            let mut progress = false;
+
+            let cores = self
+                .ready
+                .iter()
+                .map(|(core, _)| *core)
+                .chain(self.stacks.keys().copied())
+                .collect::<Vec<_>>();
+            for core in cores {
+                progress |= self.step(core);
+            }
+
+            if !progress {
+                break;
+            }
+        }
+    }
+
+    /// Makes one scheduling decision or executes one instantaneous step on `core`; returns
+    /// `false` if there's nothing to do at the current time
+    fn step(&mut self, core: Core) -> bool {
+        let stack = self.stacks.entry(core).or_default();
+        let running = stack.last().map(|job| job.threshold).unwrap_or(0);
+        let ceiling = stack
+            .iter()
+            .flat_map(|job| job.ceilings.iter().copied())
+            .max()
+            .unwrap_or(0);
+
+        // Highest priority first, then FIFO
+        let candidate = self
+            .ready
+            .iter()
+            .enumerate()
+            .filter(|(_, (job_core, job))| {
+                *job_core == core && job.priority > running && job.priority > ceiling
+            })
+            .max_by_key(|(_, (_, job))| (job.priority, cmp::Reverse(job.sequence)))
+            .map(|(index, _)| index);
+
+        if let Some(index) = candidate {
+            let (_, job) = self.ready.remove(index);
+            *self.pending.get_mut(job.task).expect("UNREACHABLE") -= 1;
+
+            // A job that's already preempted, and hasn't resumed yet, is not preempted again
+            if let Some(preempted) = self
+                .stacks
+                .get_mut(&core)
+                .and_then(|stack| stack.last_mut())
+                .filter(|job| !job.preempted)
+            {
+                preempted.preempted = true;
+                let task = preempted.task;
+                self.event(core, task, EventKind::Preempt);
+            }
+
+            self.event(core, job.task, EventKind::Start);
+            self.stacks.get_mut(&core).expect("UNREACHABLE").push(job);
+
+            return true;
+        }
+
+        let job = match self
+            .stacks
+            .get_mut(&core)
+            .and_then(|stack| stack.last_mut())
+        {
+            Some(job) => job,
+            None => return false,
+        };
+        let (task, steps) = (job.task, job.steps);
+
+        if job.preempted {
+            job.preempted = false;
+            self.event(core, task, EventKind::Resume);
+            return true;
+        }
+
+        match steps.first() {
+            // Still running
+            Some(Step::Run(_)) if !job.remaining.is_zero() => false,
+
+            Some(Step::Run(_)) => {
+                job.steps = &job.steps[1..];
+                job.remaining = first_run(job.steps);
+                true
+            }
+
+            Some(Step::Lock(resource)) => {
+                let access = self.tasks[task].shared_resources[resource].access;
+                let ceiling = match self.analysis.ownerships.get(resource) {
+                    Some(Ownership::Contended {
+                        ceiling,
+                        read_ceiling,
+                    }) => match access {
+                        Access::Exclusive => *ceiling,
+                        Access::Shared => *read_ceiling,
+                    },
+                    _ => job.priority,
+                };
+
+                job.ceilings.push(ceiling);
+                job.steps = &job.steps[1..];
+                job.remaining = first_run(job.steps);
+                self.event(core, task, EventKind::Lock(resource.clone()));
+                true
+            }
+
+            Some(Step::Unlock(resource)) => {
+                job.ceilings.pop();
+                job.steps = &job.steps[1..];
+                job.remaining = first_run(job.steps);
+                self.event(core, task, EventKind::Unlock(resource.clone()));
+                true
+            }
+
+            Some(Step::Spawn(spawnee)) => {
+                job.steps = &job.steps[1..];
+                job.remaining = first_run(job.steps);
+                self.release(spawnee);
+                true
+            }
+
+            None => {
+                let job = self
+                    .stacks
+                    .get_mut(&core)
+                    .and_then(|stack| stack.pop())
+                    .expect("UNREACHABLE");
+
+                self.event(core, task, EventKind::Finish);
+                if matches!(job.deadline, Some(deadline) if self.time > deadline) {
+                    self.event(core, task, EventKind::DeadlineMiss);
+                }
+
+                true
+            }
+        }
+    }
+}
+
+/// The duration of the first step, if it's a `Run` step
+fn first_run(steps: &[Step]) -> Duration {
+    match steps.first() {
+        Some(Step::Run(duration)) => *duration,
+        _ => Duration::ZERO,
+    }
+}
@@ -1 +1,14 @@
+mod body;
+mod cfg;
+mod device;
+mod dot;
+#[cfg(feature = "serde")]
+mod export;
+mod fuzz;
+mod lint;
+mod multi;
+mod print;
+mod schedule;
+mod sim;
 mod single;
+mod threshold;
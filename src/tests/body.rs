@@ -0,0 +1,311 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{
+    analyze::Analysis,
+    lint::{Level, Lint, Lints},
+    Settings, P,
+};
+
+fn analysis(app: TokenStream, settings: Settings) -> P<Analysis> {
+    crate::parse2(quote!(dispatchers = [A, B]), app, settings)
+        .unwrap()
+        .1
+}
+
+/// The warnings of the lints about bodies
+fn warnings(analysis: &Analysis) -> Vec<(Lint, String)> {
+    analysis
+        .warnings
+        .iter()
+        .filter(|warning| {
+            matches!(
+                warning.lint,
+                Lint::UnusedResourceAccess | Lint::UndeclaredResourceAccess | Lint::NeverSpawned
+            )
+        })
+        .map(|warning| (warning.lint, warning.message.clone()))
+        .collect()
+}
+
+fn names(set: &crate::Set<syn::Ident>) -> Vec<String> {
+    set.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn uses_and_spawns() {
+    let analysis = analysis(
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                    b: u32,
+                }
+
+                #[local]
+                struct Local {
+                    l: u32,
+                }
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {
+                    foo::spawn(0).ok();
+                }
+
+                #[idle(shared = [a], local = [l])]
+                fn idle(cx: idle::Context) -> ! {
+                    *cx.local.l += 1;
+                    defmt::info!("{}", cx.shared.a);
+                    loop {}
+                }
+
+                #[task(shared = [a, b])]
+                fn foo(mut cx: foo::Context, x: u32) {
+                    cx.shared.a.lock(|a| *a += x);
+                    let b = cx.shared.b;
+                    bar::Mono::spawn_after(1.secs()).ok();
+                }
+
+                #[task]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings::default(),
+    );
+
+    let init = &analysis.bodies[0];
+    assert_eq!(names(&init.spawns), ["foo"]);
+    assert!(init.timed_spawns.is_empty());
+
+    let idle = &analysis.bodies[1];
+    assert_eq!(names(&idle.shared_resources), ["a"]);
+    assert_eq!(names(&idle.local_resources), ["l"]);
+    assert!(!idle.opaque);
+
+    let foo = &analysis.bodies[2];
+    assert_eq!(names(&foo.shared_resources), ["a", "b"]);
+    assert_eq!(names(&foo.spawns), ["bar"]);
+    assert_eq!(names(&foo.timed_spawns), ["bar"]);
+
+    assert!(warnings(&analysis).is_empty());
+}
+
+#[test]
+fn opaque() {
+    let analysis = analysis(
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {
+                    foo::spawn().ok();
+                    bar::spawn().ok();
+                }
+
+                #[task(shared = [a])]
+                fn foo(cx: foo::Context) {
+                    helper(cx);
+                }
+
+                #[task(shared = [a])]
+                fn bar(bar::Context { shared, .. }: bar::Context) {
+                    shared.a.lock(|_| {});
+                }
+            }
+        ),
+        Settings::default(),
+    );
+
+    assert!(analysis.bodies[1].opaque);
+    assert!(analysis.bodies[2].opaque);
+    assert!(warnings(&analysis).is_empty());
+}
+
+#[test]
+fn peripherals() {
+    let analysis = analysis(
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                }
+
+                #[local]
+                struct Local {
+                    l: u32,
+                }
+
+                #[init(local = [n: u32 = 0])]
+                fn init(cx: init::Context) -> (Shared, Local, init::Monotonics) {
+                    let gpioa = cx.device.GPIOA;
+                    cx.core.SCB.set_sleepdeep();
+                    defmt::info!("{}", cx.device.RCC.cfgr);
+                    let _ = cx.cs;
+                }
+
+                #[idle(local = [l])]
+                fn idle(cx: idle::Context) -> ! {
+                    let _ = cx.core;
+                    loop {}
+                }
+            }
+        ),
+        Settings::default(),
+    );
+
+    // Peripherals aren't resources, the unused `n` and `l` are still reported
+    assert!(!analysis.bodies[0].opaque);
+    assert!(!analysis.bodies[1].opaque);
+    assert_eq!(
+        warnings(&analysis)
+            .into_iter()
+            .map(|(_, message)| message)
+            .collect::<Vec<_>>(),
+        [
+            "`init` has access to the local resource `n` but never uses it",
+            "`idle` has access to the local resource `l` but never uses it",
+        ]
+    );
+}
+
+#[test]
+fn lints() {
+    let analysis = analysis(
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    a: u32,
+                    b: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {
+                    foo::spawn().ok();
+                }
+
+                #[task(shared = [a], local = [n: u32 = 0])]
+                fn foo(cx: foo::Context) {
+                    cx.shared.b.lock(|_| {});
+                }
+
+                #[task(shared = [a, b])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings {
+            lints: Lints {
+                never_spawned: Level::Warn,
+                ..Lints::default()
+            },
+            ..Settings::default()
+        },
+    );
+
+    assert_eq!(
+        warnings(&analysis),
+        [
+            (
+                Lint::UnusedResourceAccess,
+                "`foo` has access to the shared resource `a` but never uses it".to_string()
+            ),
+            (
+                Lint::UnusedResourceAccess,
+                "`foo` has access to the local resource `n` but never uses it".to_string()
+            ),
+            (
+                Lint::UndeclaredResourceAccess,
+                "`foo` uses the shared resource `b` but doesn't list it in its `shared` argument"
+                    .to_string()
+            ),
+            (
+                Lint::NeverSpawned,
+                "software task `bar` is never spawned".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn user_code_spawns() {
+    let analysis = analysis(
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task]
+                fn foo(_: foo::Context) {}
+
+                fn helper() {
+                    foo::spawn().ok();
+                }
+            }
+        ),
+        Settings::default(),
+    );
+
+    assert_eq!(names(&analysis.user_code_spawns), ["foo"]);
+    assert!(warnings(&analysis).is_empty());
+}
+
+fn send_types(spawner: TokenStream, precise_send_types: bool) -> usize {
+    analysis(
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {
+                    bar::spawn().ok();
+                }
+
+                #[task]
+                fn foo(_: foo::Context, x: Rc<u32>) {}
+
+                #[task(#spawner)]
+                fn bar(_: bar::Context) {
+                    foo::spawn(Rc::new(0)).ok();
+                }
+            }
+        ),
+        Settings {
+            precise_send_types,
+            ..Settings::default()
+        },
+    )
+    .send_types
+    .len()
+}
+
+#[test]
+fn precise_send_types() {
+    // Same priority
+    assert_eq!(send_types(quote!(priority = 1), false), 1);
+    assert_eq!(send_types(quote!(priority = 1), true), 0);
+
+    // Spawned from another priority
+    assert_eq!(send_types(quote!(priority = 2), true), 1);
+}
@@ -0,0 +1,679 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::{parse_quote, Attribute};
+
+use crate::{
+    analyze::{Analysis, Handler, Location, Ownership},
+    ast::App,
+    cfg::{Cfg, CfgSet, Gated},
+    lint::Lint,
+    Settings, P,
+};
+
+fn cfg(attrs: &[Attribute]) -> Cfg {
+    Cfg::from_attrs(attrs)
+}
+
+#[test]
+fn from_attrs() {
+    let radio = cfg(&[parse_quote!(#[cfg(feature = "radio")])]);
+    assert_eq!(
+        radio,
+        Cfg::Option {
+            name: "feature".to_string(),
+            value: Some("radio".to_string())
+        }
+    );
+
+    // Several `#[cfg]`s must all hold
+    let both = cfg(&[
+        parse_quote!(#[cfg(feature = "radio")]),
+        parse_quote!(#[cfg(not(any(debug_assertions, test)))]),
+    ]);
+    assert_eq!(
+        both.to_token_stream().to_string(),
+        quote!(all(feature = "radio", not(any(debug_assertions, test)))).to_string()
+    );
+    assert_eq!(
+        both.to_string(),
+        r#"all(feature = "radio", not(any(debug_assertions, test)))"#
+    );
+
+    assert!(cfg(&[parse_quote!(#[inline])]).is_always());
+}
+
+#[test]
+fn satisfiability() {
+    let a = cfg(&[parse_quote!(#[cfg(feature = "a")])]);
+    let not_a = cfg(&[parse_quote!(#[cfg(not(feature = "a"))])]);
+    let a_or_b = cfg(&[parse_quote!(#[cfg(any(feature = "a", feature = "b"))])]);
+    let arm = cfg(&[parse_quote!(#[cfg(target_arch = "arm")])]);
+    let riscv = cfg(&[parse_quote!(#[cfg(target_arch = "riscv32")])]);
+
+    assert!(a.is_satisfiable());
+    assert!(!a.clone().and(not_a.clone()).is_satisfiable());
+    assert!(!Cfg::Any(vec![]).is_satisfiable());
+
+    assert!(a.implies(&a_or_b));
+    assert!(!a_or_b.implies(&a));
+    assert!(a.implies(&Cfg::always()));
+
+    assert!(a.overlaps(&a_or_b));
+    assert!(!a.overlaps(&not_a));
+
+    // There's only one `target_arch`
+    assert!(!arm.and(riscv).is_satisfiable());
+}
+
+#[test]
+fn gated_analysis() {
+    let (_, analysis) = crate::parse2(
+        quote!(dispatchers = [A, B]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    x: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {
+                    foo::spawn(0).ok();
+                }
+
+                #[task(capacity = 2, shared = [x])]
+                fn foo(_: foo::Context, _: u32) {}
+
+                #[cfg(feature = "radio")]
+                #[task(capacity = 3, shared = [x])]
+                fn radio(_: radio::Context, _: Packet) {}
+
+                #[task(priority = 2, shared = [x])]
+                #[cfg(feature = "debug")]
+                fn debug(_: debug::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let radio = cfg(&[parse_quote!(#[cfg(feature = "radio")])]);
+    let debug = cfg(&[parse_quote!(#[cfg(feature = "debug")])]);
+
+    // With every feature enabled
+    assert_eq!(
+        analysis.ownerships[0],
+        Ownership::Contended {
+            ceiling: 2,
+            read_ceiling: 2
+        }
+    );
+
+    // Without `debug` the ceiling is 1, and without `radio` `foo` owns `x`
+    let gated = &analysis.gated_ownerships[&format_ident!("x")];
+    assert_eq!(gated.len(), 3);
+    for Gated { cfg, value } in gated {
+        let expected = match (cfg.implies(&radio), cfg.implies(&debug)) {
+            (_, true) => Ownership::Contended {
+                ceiling: 2,
+                read_ceiling: 2,
+            },
+            (true, false) => Ownership::CoOwned { priority: 1 },
+            (false, false) => Ownership::Owned { priority: 1 },
+        };
+        assert_eq!(*value, expected, "cfg({})", cfg);
+    }
+    assert!(gated.iter().all(|a| gated
+        .iter()
+        .all(|b| a == b || !a.cfg.clone().and(b.cfg.clone()).is_satisfiable())));
+
+    let channel = &analysis.channels[&0][&1];
+    assert_eq!(channel.capacity, 5);
+    assert_eq!(
+        channel.capacities,
+        [
+            Gated {
+                cfg: Cfg::always(),
+                value: 2
+            },
+            Gated {
+                cfg: radio.clone(),
+                value: 3
+            }
+        ]
+    );
+
+    let send_types = analysis
+        .send_types
+        .iter()
+        .map(|(ty, cfg)| (quote!(#ty).to_string(), cfg.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        send_types,
+        [
+            ("u32".to_string(), Cfg::always()),
+            ("Packet".to_string(), radio),
+        ]
+    );
+}
+
+fn pruned(set: CfgSet) -> Result<P<App>, String> {
+    crate::parse2(
+        quote!(dispatchers = [A]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    #[cfg(feature = "radio")]
+                    packets: u32,
+                    #[cfg(debug_assertions)]
+                    log: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init(local = [
+                    #[cfg(not(feature = "radio"))]
+                    idle_count: u32 = 0,
+                    n: u32 = 0,
+                ])]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[cfg(feature = "radio")]
+                #[task(shared = [packets])]
+                fn radio(_: radio::Context) {}
+
+                #[cfg(not(feature = "radio"))]
+                #[task(binds = UART0)]
+                fn uart(_: uart::Context) {}
+            }
+        ),
+        Settings {
+            parse_binds: true,
+            cfg: Some(set),
+            ..Settings::default()
+        },
+    )
+    .map(|(app, _)| app)
+    .map_err(|e| e.to_string())
+}
+
+#[test]
+fn prune() {
+    let mut set = CfgSet::default();
+    set.insert_feature("radio");
+    let app = pruned(set).unwrap();
+    assert_eq!(app.shared_resources.keys().collect::<Vec<_>>(), ["packets"]);
+    assert!(app.software_tasks.contains_key(&format_ident!("radio")));
+    assert!(app.hardware_tasks.is_empty());
+    assert_eq!(
+        app.init.args.local_resources.keys().collect::<Vec<_>>(),
+        ["n"]
+    );
+
+    let mut set = CfgSet::default();
+    set.insert_spec("debug_assertions").unwrap();
+    let app = pruned(set).unwrap();
+    assert_eq!(app.shared_resources.keys().collect::<Vec<_>>(), ["log"]);
+    assert!(app.software_tasks.is_empty());
+    assert!(app.hardware_tasks.contains_key(&format_ident!("uart")));
+    assert_eq!(app.init.args.local_resources.len(), 2);
+}
+
+#[test]
+fn spec() {
+    let mut set = CfgSet::default();
+    set.insert_spec(r#"target_os = "none""#).unwrap();
+    assert!(set.contains("target_os", Some("none")));
+    assert!(set.holds(&cfg(&[
+        parse_quote!(#[cfg(all(target_os = "none", not(test)))])
+    ])));
+
+    assert!(set.insert_spec("feature = 1").is_err());
+    assert!(set.insert_spec("not(test)").is_err());
+}
+
+#[test]
+fn inconsistent_cfg() {
+    let (_, analysis) = crate::parse2(
+        quote!(dispatchers = [A]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    #[cfg(feature = "radio")]
+                    packets: u32,
+                }
+
+                #[local]
+                struct Local {
+                    #[cfg(any(feature = "radio", feature = "usb"))]
+                    buffer: [u8; 64],
+                }
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {
+                    foo::spawn().ok();
+                    bar::spawn().ok();
+                }
+
+                #[cfg(feature = "radio")]
+                #[task(shared = [packets], local = [buffer])]
+                fn foo(_: foo::Context) {}
+
+                #[cfg(any(feature = "radio", debug_assertions))]
+                #[task(shared = [packets])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let warnings = analysis
+        .warnings
+        .iter()
+        .filter(|warning| warning.lint == Lint::InconsistentCfg)
+        .map(|warning| (warning.message.clone(), warning.notes[0].1.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        warnings,
+        [(
+            "`bar` can exist without the shared resource `packets` it lists".to_string(),
+            r#"the shared resource `packets` only exists with `cfg(feature = "radio")`"#
+                .to_string()
+        )]
+    );
+}
+
+fn entries(settings: Settings) -> (P<App>, P<Analysis>) {
+    crate::parse2(
+        quote!(dispatchers = [A, B]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    x: u32,
+                    #[cfg(feature = "log")]
+                    log: u32,
+                }
+
+                #[local]
+                struct Local {
+                    #[cfg(feature = "log")]
+                    buffer: [u8; 64],
+                }
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {
+                    foo::spawn().ok();
+                    bar::spawn().ok();
+                }
+
+                #[task(shared = [x, #[cfg(feature = "log")] &log])]
+                fn foo(_: foo::Context) {}
+
+                #[task(
+                    priority = 2,
+                    shared = [#[cfg(feature = "fast")] x, log],
+                    local = [#[cfg(feature = "log")] buffer],
+                )]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        settings,
+    )
+    .unwrap()
+}
+
+#[test]
+fn entry_cfgs() {
+    let (app, analysis) = entries(Settings::default());
+
+    let foo = &app
+        .software_tasks
+        .variants(&format_ident!("foo"))
+        .next()
+        .unwrap()
+        .args;
+    assert!(foo.shared_resources[&format_ident!("x")].cfgs.is_empty());
+    assert_eq!(foo.shared_resources[&format_ident!("log")].cfgs.len(), 1);
+    let bar = &app
+        .software_tasks
+        .variants(&format_ident!("bar"))
+        .next()
+        .unwrap()
+        .args;
+    assert_eq!(
+        bar.local_resources[&format_ident!("buffer")].cfgs().len(),
+        1
+    );
+
+    // `bar` only contends for `x` with the `fast` feature
+    let fast = cfg(&[parse_quote!(#[cfg(feature = "fast")])]);
+    let gated = &analysis.gated_ownerships[&format_ident!("x")];
+    assert_eq!(gated.len(), 2);
+    for Gated { cfg, value } in gated {
+        let expected = if cfg.implies(&fast) {
+            Ownership::Contended {
+                ceiling: 2,
+                read_ceiling: 2,
+            }
+        } else {
+            Ownership::Owned { priority: 1 }
+        };
+        assert_eq!(*value, expected, "cfg({})", cfg);
+    }
+
+    // `bar` lists `log` unconditionally, `foo` and the `buffer` entry are gated like the resources
+    let warnings = analysis
+        .warnings
+        .iter()
+        .filter(|warning| warning.lint == Lint::InconsistentCfg)
+        .map(|warning| warning.message.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        warnings,
+        ["`bar` can exist without the shared resource `log` it lists"]
+    );
+}
+
+#[test]
+fn prune_entries() {
+    let mut set = CfgSet::default();
+    set.insert_feature("log");
+    let (app, _) = entries(Settings {
+        cfg: Some(set),
+        ..Settings::default()
+    });
+
+    let foo = &app
+        .software_tasks
+        .variants(&format_ident!("foo"))
+        .next()
+        .unwrap()
+        .args;
+    assert_eq!(
+        foo.shared_resources.keys().collect::<Vec<_>>(),
+        ["x", "log"]
+    );
+    let bar = &app
+        .software_tasks
+        .variants(&format_ident!("bar"))
+        .next()
+        .unwrap()
+        .args;
+    assert_eq!(bar.shared_resources.keys().collect::<Vec<_>>(), ["log"]);
+    assert_eq!(bar.local_resources.keys().collect::<Vec<_>>(), ["buffer"]);
+}
+
+fn variants(tasks: TokenStream, settings: Settings) -> Result<(P<App>, P<Analysis>), String> {
+    crate::parse2(
+        quote!(dispatchers = [A]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #tasks
+            }
+        ),
+        Settings {
+            parse_binds: true,
+            ..settings
+        },
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[test]
+fn exclusive_bindings() {
+    let (_, analysis) = variants(
+        quote!(
+            #[cfg(any(feature = "a", feature = "b"))]
+            #[task(binds = UART0)]
+            fn uart_ab(_: uart_ab::Context) {}
+
+            #[cfg(not(any(feature = "a", feature = "b")))]
+            #[task(binds = UART0)]
+            fn uart(_: uart::Context) {}
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let handlers = analysis.vector_table[&0][&format_ident!("UART0")]
+        .handlers
+        .iter()
+        .map(|handler| (handler.cfg.to_string(), handler.value.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        handlers,
+        [
+            (
+                r#"any(feature = "a", feature = "b")"#.to_string(),
+                Handler::HardwareTask(format_ident!("uart_ab"))
+            ),
+            (
+                r#"not(any(feature = "a", feature = "b"))"#.to_string(),
+                Handler::HardwareTask(format_ident!("uart"))
+            ),
+        ]
+    );
+
+    // Both can be enabled without `a` and with `b`
+    let err = variants(
+        quote!(
+            #[cfg(any(feature = "a", feature = "b"))]
+            #[task(binds = UART0)]
+            fn uart_ab(_: uart_ab::Context) {}
+
+            #[cfg(not(feature = "a"))]
+            #[task(binds = UART0)]
+            fn uart(_: uart::Context) {}
+        ),
+        Settings::default(),
+    )
+    .err()
+    .unwrap();
+    assert_eq!(err, "this interrupt is already bound");
+}
+
+#[test]
+fn exclusive_task_names() {
+    let tasks = quote!(
+        #[cfg(all(feature = "a", feature = "b"))]
+        #[task(binds = UART0)]
+        fn uart(_: uart::Context) {}
+
+        #[cfg(not(all(feature = "a", feature = "b")))]
+        #[task(binds = UART1)]
+        fn uart(_: uart::Context) {}
+    );
+
+    // Both variants are kept, each binding its own interrupt
+    let (app, analysis) = variants(tasks.clone(), Settings::default()).unwrap();
+    let uart = format_ident!("uart");
+    assert_eq!(
+        app.hardware_tasks
+            .variants(&uart)
+            .map(|task| task.args.binds.to_string())
+            .collect::<Vec<_>>(),
+        ["UART0", "UART1"]
+    );
+    for vector in ["UART0", "UART1"] {
+        assert_eq!(
+            analysis.vector_table[&0][&format_ident!("{}", vector)].handlers[0].value,
+            Handler::HardwareTask(uart.clone())
+        );
+    }
+
+    // `Settings::cfg` selects one of them
+    let mut set = CfgSet::default();
+    set.insert_feature("a");
+    let (app, _) = variants(
+        tasks,
+        Settings {
+            cfg: Some(set),
+            ..Settings::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(app.hardware_tasks.len(), 1);
+    assert_eq!(
+        app.hardware_tasks
+            .variants(&uart)
+            .next()
+            .unwrap()
+            .args
+            .binds,
+        "UART1"
+    );
+
+    // Tasks that can be enabled together still can't share a name
+    let err = variants(
+        quote!(
+            #[cfg(feature = "a")]
+            #[task]
+            fn foo(_: foo::Context) {}
+
+            #[cfg(feature = "b")]
+            #[task]
+            fn foo(_: foo::Context) {}
+        ),
+        Settings::default(),
+    )
+    .err()
+    .unwrap();
+    assert_eq!(err, "this task is defined multiple times");
+}
+
+#[test]
+fn accesses_excluded_by_resource_cfg() {
+    // `bar` only writes `x` when `x` doesn't exist, so `x` is read-only and stays on core 0
+    let (_app, analysis) = crate::parse2(
+        quote!(cores = 2),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    #[lock_free]
+                    #[cfg(feature = "a")]
+                    x: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(binds = UART0, shared = [#[cfg(feature = "a")] &x])]
+                fn foo(_: foo::Context) {}
+
+                #[task(binds = UART1, core = 1, shared = [#[cfg(not(feature = "a"))] x])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings {
+            parse_binds: true,
+            ..Settings::default()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        analysis.locations[&format_ident!("x")],
+        Location::Owned { core: 0 }
+    );
+}
+
+#[test]
+fn exclusive_users() {
+    // `foo` and `bar` are never compiled together so they can use the same `#[local]` resource and
+    // write a `#[lock_free]` resource at different priorities
+    let tasks = |cfg_bar: TokenStream| {
+        crate::parse2(
+            quote!(dispatchers = [A, B]),
+            quote!(
+                mod app {
+                    #[shared]
+                    struct Shared {
+                        #[lock_free]
+                        x: u32,
+                    }
+
+                    #[local]
+                    struct Local {
+                        buffer: [u8; 64],
+                    }
+
+                    #[init]
+                    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                    #[cfg(feature = "a")]
+                    #[task(shared = [x], local = [buffer])]
+                    fn foo(_: foo::Context) {}
+
+                    #[cfg(#cfg_bar)]
+                    #[task(priority = 2, shared = [x], local = [buffer])]
+                    fn bar(_: bar::Context) {}
+                }
+            ),
+            Settings::default(),
+        )
+        .err()
+        .map(|error| error.to_string())
+    };
+
+    assert_eq!(tasks(quote!(not(feature = "a"))), None);
+    assert_eq!(
+        tasks(quote!(feature = "b")).as_deref(),
+        Some("Lock free shared resource \"x\" is used by tasks at different priorities")
+    );
+}
+
+#[test]
+fn variant_capacities() {
+    let capacity = |tasks: TokenStream| {
+        variants(tasks, Settings::default()).map(|(_, analysis)| analysis.channels[&0][&1].capacity)
+    };
+
+    // The variants of `foo` never exist together
+    assert_eq!(
+        capacity(quote!(
+            #[cfg(feature = "a")]
+            #[task(capacity = 3)]
+            fn foo(_: foo::Context) {}
+
+            #[cfg(not(feature = "a"))]
+            #[task(capacity = 5)]
+            fn foo(_: foo::Context) {}
+
+            #[task(capacity = 2)]
+            fn bar(_: bar::Context) {}
+        )),
+        Ok(7)
+    );
+
+    assert_eq!(
+        capacity(quote!(
+            #[task(capacity = 200)]
+            fn foo(_: foo::Context) {}
+
+            #[task(capacity = 100)]
+            fn bar(_: bar::Context) {}
+        )),
+        Err("the capacities of the tasks of priority 1 add up to more than 255".to_string())
+    );
+}
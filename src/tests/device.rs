@@ -0,0 +1,296 @@
+use quote::{format_ident, quote};
+
+use crate::{
+    analyze::{Handler, Vector},
+    ast::Binding,
+    cfg::{Cfg, Gated},
+    device::{Device, Error},
+    Settings,
+};
+
+fn device() -> Device {
+    Device::parse(
+        "
+        # core exceptions
+        exception HardFault fixed
+        exception SysTick
+        exception PendSV
+
+        interrupt USART1 # serial
+        interrupt USART2
+        interrupt EXTI0
+        ",
+    )
+    .unwrap()
+}
+
+fn check(args: proc_macro2::TokenStream, binds: proc_macro2::TokenStream) -> Result<(), String> {
+    check_with(args, binds, device())
+}
+
+fn check_with(
+    args: proc_macro2::TokenStream,
+    binds: proc_macro2::TokenStream,
+    device: Device,
+) -> Result<(), String> {
+    crate::parse2(
+        args,
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[monotonic(binds = SysTick)]
+                type Mono = Systick;
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(binds = #binds)]
+                fn foo(_: foo::Context) {}
+
+                #[task]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings {
+            parse_binds: true,
+            device: Some(device),
+            ..Settings::default()
+        },
+    )
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+#[test]
+fn parse() {
+    let device = device();
+
+    assert_eq!(device.exceptions, ["HardFault", "SysTick", "PendSV"]);
+    assert_eq!(device.fixed_priority, ["HardFault"]);
+    assert_eq!(device.interrupts, ["USART1", "USART2", "EXTI0"]);
+
+    assert_eq!(device.binding("USART1"), Binding::Interrupt);
+    assert_eq!(device.binding("SysTick"), Binding::Exception);
+    assert_eq!(device.binding("HardFault"), Binding::FixedPriorityException);
+
+    assert_eq!(
+        Device::parse("interrupt A\nirq B"),
+        Err(Error::Malformed { line: 2 })
+    );
+    assert_eq!(
+        Device::parse("interrupt A fixed"),
+        Err(Error::Malformed { line: 1 })
+    );
+    assert_eq!(
+        Device::parse("interrupt A\nexception A"),
+        Err(Error::Duplicate {
+            line: 2,
+            name: "A".to_string()
+        })
+    );
+}
+
+#[test]
+fn valid() {
+    assert_eq!(check(quote!(dispatchers = [EXTI0]), quote!(USART1)), Ok(()));
+    assert_eq!(check(quote!(dispatchers = [EXTI0]), quote!(PendSV)), Ok(()));
+}
+
+#[test]
+fn typo() {
+    assert_eq!(
+        check(quote!(dispatchers = [EXTI0]), quote!(USRT1)),
+        Err(
+            "`USRT1` is not an interrupt or exception of the device; did you mean `USART1`?"
+                .to_string()
+        )
+    );
+    assert_eq!(
+        check(quote!(dispatchers = [EXTI0]), quote!(UART0)),
+        Err("`UART0` is not an interrupt or exception of the device".to_string())
+    );
+}
+
+#[test]
+fn exception_as_dispatcher() {
+    assert_eq!(
+        check(quote!(dispatchers = [PendSV]), quote!(USART1)),
+        Err(
+            "`PendSV` is a core exception, only device interrupts can be used as dispatchers"
+                .to_string()
+        )
+    );
+}
+
+#[test]
+fn fixed_priority() {
+    assert_eq!(
+        check(
+            quote!(dispatchers = [EXTI0]),
+            quote!(HardFault, priority = 2)
+        ),
+        Err(
+            "`HardFault` is an exception with a fixed priority, it can't take a `priority`"
+                .to_string()
+        )
+    );
+    assert_eq!(
+        check(quote!(dispatchers = [EXTI0]), quote!(HardFault)),
+        Ok(())
+    );
+}
+
+#[test]
+fn cortex_m() {
+    // Without interrupts the device only classifies the bindings
+    assert_eq!(
+        check_with(
+            quote!(dispatchers = [EXTI0]),
+            quote!(UART0),
+            Device::cortex_m()
+        ),
+        Ok(())
+    );
+    assert!(check_with(
+        quote!(dispatchers = [PendSV]),
+        quote!(UART0),
+        Device::cortex_m()
+    )
+    .is_err());
+}
+
+#[test]
+fn vector_table() {
+    let (app, analysis) = crate::parse2(
+        quote!(dispatchers = [EXTI0, USART2]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[monotonic(binds = SysTick)]
+                type Mono = Systick;
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {
+                    bar::spawn().ok();
+                }
+
+                #[task(binds = USART1)]
+                fn foo(_: foo::Context) {}
+
+                #[task(binds = HardFault)]
+                fn fault(_: fault::Context) {}
+
+                #[task]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings {
+            parse_binds: true,
+            device: Some(device()),
+            ..Settings::default()
+        },
+    )
+    .unwrap();
+
+    let foo = &app
+        .hardware_tasks
+        .variants(&format_ident!("foo"))
+        .next()
+        .unwrap();
+    assert_eq!(foo.binding, Binding::Interrupt);
+    let mono = &app.monotonics[&format_ident!("Mono")];
+    assert_eq!(mono.binding, Binding::Exception);
+
+    let vectors = analysis.vector_table[&0]
+        .iter()
+        .map(|(name, vector)| (name.to_string(), vector.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vectors,
+        [
+            (
+                "USART1".to_string(),
+                Vector {
+                    binding: Binding::Interrupt,
+                    handlers: vec![Gated {
+                        cfg: Cfg::always(),
+                        value: Handler::HardwareTask(format_ident!("foo")),
+                    }],
+                }
+            ),
+            (
+                "HardFault".to_string(),
+                Vector {
+                    binding: Binding::FixedPriorityException,
+                    handlers: vec![Gated {
+                        cfg: Cfg::always(),
+                        value: Handler::HardwareTask(format_ident!("fault")),
+                    }],
+                }
+            ),
+            (
+                "SysTick".to_string(),
+                Vector {
+                    binding: Binding::Exception,
+                    handlers: vec![Gated {
+                        cfg: Cfg::always(),
+                        value: Handler::Monotonic(format_ident!("Mono")),
+                    }],
+                }
+            ),
+            (
+                "EXTI0".to_string(),
+                Vector {
+                    binding: Binding::Interrupt,
+                    handlers: vec![Gated {
+                        cfg: Cfg::always(),
+                        value: Handler::Dispatcher(1),
+                    }],
+                }
+            ),
+        ]
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn json() {
+    let device =
+        Device::from_json(r#"{ "interrupts": ["USART1"], "exceptions": ["SysTick"] }"#).unwrap();
+
+    assert_eq!(device.interrupts, ["USART1"]);
+    assert_eq!(device.exceptions, ["SysTick"]);
+    assert!(device.fixed_priority.is_empty());
+    assert!(Device::from_json(r#"{ "interrupts": "USART1" }"#).is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn json_fixed_priority() {
+    let error = Device::from_json(
+        r#"{ "interrupts": ["USART1"], "exceptions": ["HardFault"], "fixed_priority": ["USART1"] }"#,
+    )
+    .err()
+    .unwrap();
+
+    assert_eq!(
+        error,
+        Error::NotAnException {
+            name: "USART1".to_string()
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "`USART1` has a fixed priority but isn't listed in `exceptions`"
+    );
+}
@@ -0,0 +1,56 @@
+use quote::quote;
+
+use crate::Settings;
+
+#[test]
+fn dot() {
+    let (app, analysis) = crate::parse2(
+        quote!(dispatchers = [A]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    x: u32,
+                    y: u32,
+                    z: u32,
+                }
+
+                #[local]
+                struct Local {
+                    l: u32,
+                }
+
+                #[init(local = [a: u32 = 0])]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {
+                    bar::spawn().ok();
+                }
+
+                #[task(binds = UART0, priority = 2, shared = [x, &y], local = [l])]
+                fn foo(_: foo::Context) {}
+
+                #[task(shared = [&x, &y])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings {
+            parse_binds: true,
+            ..Settings::default()
+        },
+    )
+    .unwrap();
+
+    let dot = analysis.to_dot(&app);
+
+    assert!(dot.starts_with("digraph \"app\" {\n"));
+    assert!(dot.contains("    subgraph cluster_priority_1 {\n        label=\"priority 1\";\n        \"task bar\" [label=\"bar\\n(software task)\", shape=box];\n    }\n"));
+    assert!(dot.contains("\"task foo\" [label=\"foo\\n(binds UART0)\", shape=box];"));
+    assert!(dot.contains("\"shared x\" [label=\"x: u32\\nceiling 2\", shape=ellipse];"));
+    assert!(dot.contains("\"shared y\" [label=\"y: u32\\nread-only\", shape=ellipse];"));
+    assert!(dot.contains("\"shared z\" [label=\"z: u32\", shape=ellipse, style=dotted];"));
+    assert!(dot.contains("\"task foo\" -> \"shared x\" [label=\"exclusive\", style=solid];"));
+    assert!(dot.contains("\"task bar\" -> \"shared x\" [label=\"shared\", style=dashed];"));
+    assert!(dot.contains("\"task foo\" -> \"local l\";"));
+    assert!(dot.contains("\"task init\" -> \"local init::a\";"));
+    assert!(dot.contains("\"task init\" -> \"task bar\" [label=\"spawn\", style=bold];"));
+    assert!(dot.ends_with("}\n"));
+}
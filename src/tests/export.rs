@@ -0,0 +1,185 @@
+use proc_macro2::TokenStream;
+use serde_json::Value;
+
+use crate::{export::Document, Settings};
+
+#[test]
+fn json() {
+    let input: TokenStream = "
+mod app {
+    #[shared]
+    struct Shared {
+        x: u32,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+    #[task(binds = UART0, priority = 2, shared = [x])]
+    fn foo(_: foo::Context) {}
+
+    #[task(capacity = 2, shared = [&x])]
+    fn bar(_: bar::Context, _: u32) {}
+}"
+    .parse()
+    .unwrap();
+
+    let (app, analysis) = crate::parse2(
+        "dispatchers = [EXTI0]".parse().unwrap(),
+        input,
+        Settings {
+            parse_binds: true,
+            ..Settings::default()
+        },
+    )
+    .unwrap();
+
+    let json: Value =
+        serde_json::from_str(&Document::new(&app, &analysis, "src/main.rs").to_json()).unwrap();
+
+    assert_eq!(json["schema_version"], 1);
+    assert_eq!(json["app"]["dispatchers"][0], "EXTI0");
+
+    let foo = &json["app"]["hardware_tasks"][0];
+    assert_eq!(foo["name"], "foo");
+    assert_eq!(foo["kind"], "hardware");
+    assert_eq!(foo["binds"], "UART0");
+    assert_eq!(foo["location"]["file"], "src/main.rs");
+    assert_eq!(foo["location"]["line"], 15);
+    assert_eq!(foo["location"]["column"], 8);
+
+    let bar = &json["app"]["software_tasks"][0];
+    assert_eq!(bar["capacity"], 2);
+    assert_eq!(bar["inputs"][0], "u32");
+    assert_eq!(bar["shared_resources"][0]["access"], "shared");
+
+    let ownership = &json["analysis"]["ownerships"][0];
+    assert_eq!(ownership["resource"], "x");
+    assert_eq!(ownership["kind"], "contended");
+    assert_eq!(ownership["ceiling"], 2);
+    assert_eq!(ownership["read_ceiling"], 2);
+
+    assert_eq!(json["analysis"]["channels"][0]["capacity"], 2);
+    assert_eq!(json["analysis"]["dispatchers"][0]["interrupt"], "EXTI0");
+    let vector = &json["analysis"]["vector_table"][0];
+    assert_eq!(vector["vector"], "UART0");
+    assert_eq!(vector["handler"]["kind"], "hardware-task");
+    assert_eq!(vector["handler"]["task"], "foo");
+    let vector = &json["analysis"]["vector_table"][1];
+    assert_eq!(vector["vector"], "EXTI0");
+    assert_eq!(vector["binding"], "interrupt");
+    assert_eq!(vector["handler"]["kind"], "dispatcher");
+    assert_eq!(vector["handler"]["priority"], 1);
+    assert_eq!(json["analysis"]["sync_types"][0], "u32");
+}
+
+#[test]
+fn cfgs() {
+    let input: TokenStream = r#"
+mod app {
+    #[shared]
+    struct Shared {
+        x: u32,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+    #[task(shared = [x])]
+    fn foo(_: foo::Context, _: u32) {}
+
+    #[cfg(feature = "a")]
+    #[task(priority = 2, shared = [x])]
+    fn bar(_: bar::Context, _: u64) {}
+
+    #[cfg(feature = "a")]
+    #[task(priority = 2)]
+    fn baz(_: baz::Context) {}
+}"#
+    .parse()
+    .unwrap();
+
+    let (app, analysis) = crate::parse2(
+        "dispatchers = [EXTI0, EXTI1]".parse().unwrap(),
+        input,
+        Settings::default(),
+    )
+    .unwrap();
+
+    let json: Value =
+        serde_json::from_str(&Document::new(&app, &analysis, "src/main.rs").to_json()).unwrap();
+
+    // `bar` and `baz` share the channel of priority 2, each adding a capacity term
+    let channel = &json["analysis"]["channels"][1];
+    assert_eq!(channel["capacity"], 2);
+    assert_eq!(channel["capacities"][0]["capacity"], 1);
+    assert_eq!(channel["capacities"][0]["cfg"], r#"feature = "a""#);
+
+    // `x` is only contended when `bar` exists
+    let gated = json["analysis"]["gated_ownerships"].as_array().unwrap();
+    assert_eq!(gated.len(), 2);
+    assert_eq!(gated[0]["resource"], "x");
+    for gated in gated {
+        let kind = if gated["cfg"] == r#"feature = "a""# {
+            "contended"
+        } else {
+            assert_eq!(gated["cfg"], r#"not(feature = "a")"#);
+            "owned"
+        };
+        assert_eq!(gated["kind"], kind);
+    }
+
+    // The input of `bar` only needs to be `Send` when `bar` exists
+    let send_types = json["analysis"]["send_types"].as_array().unwrap();
+    let u64_ = send_types.iter().find(|ty| ty["type"] == "u64").unwrap();
+    assert_eq!(u64_["cfg"], r#"feature = "a""#);
+}
+
+#[test]
+fn atomic_resources() {
+    let input: TokenStream = "
+mod app {
+    #[shared]
+    struct Shared {
+        #[atomic]
+        counter: AtomicU32,
+        x: u32,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+    #[task(shared = [&counter, x])]
+    fn foo(_: foo::Context) {}
+
+    #[task(priority = 2, shared = [&counter])]
+    fn bar(_: bar::Context) {}
+}"
+    .parse()
+    .unwrap();
+
+    let (app, analysis) = crate::parse2(
+        "dispatchers = [EXTI0, EXTI1]".parse().unwrap(),
+        input,
+        Settings::default(),
+    )
+    .unwrap();
+
+    let json: Value =
+        serde_json::from_str(&Document::new(&app, &analysis, "src/main.rs").to_json()).unwrap();
+
+    // `counter` is used but has no ownership
+    assert_eq!(json["analysis"]["atomic_resources"][0], "counter");
+    let ownerships = json["analysis"]["ownerships"].as_array().unwrap();
+    assert_eq!(ownerships.len(), 1);
+    assert_eq!(ownerships[0]["resource"], "x");
+}
@@ -0,0 +1,300 @@
+//! Property test: `parse2` must never panic, whatever token stream it is given
+
+use std::panic;
+
+use proc_macro2::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use quote::quote;
+
+use crate::{
+    cfg::CfgSet,
+    device::Device,
+    lint::{Level, Lints},
+    Settings,
+};
+
+const ITERATIONS: usize = 2_000;
+
+const IDENTS: &[&str] = &[
+    "mod",
+    "app",
+    "fn",
+    "async",
+    "struct",
+    "extern",
+    "type",
+    "use",
+    "pub",
+    "init",
+    "idle",
+    "task",
+    "shared",
+    "local",
+    "monotonic",
+    "lock_free",
+    "atomic",
+    "task_local",
+    "cfg",
+    "binds",
+    "priority",
+    "capacity",
+    "threshold",
+    "default",
+    "dispatchers",
+    "device",
+    "peripherals",
+    "Context",
+    "Monotonics",
+    "Shared",
+    "Local",
+    "foo",
+    "bar",
+    "x",
+    "y",
+    "u32",
+    "true",
+];
+
+const PUNCTS: &[char] = &['#', '=', ',', ':', ';', '&', '!', '-', '>', '.', '*'];
+
+/// Deterministic xorshift PRNG so failures are reproducible
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+fn random_tree(rng: &mut Rng, depth: usize) -> TokenTree {
+    match rng.below(if depth == 0 { 3 } else { 4 }) {
+        0 => Ident::new(IDENTS[rng.below(IDENTS.len())], Span::call_site()).into(),
+        1 => {
+            let spacing = if rng.below(2) == 0 {
+                Spacing::Alone
+            } else {
+                Spacing::Joint
+            };
+            Punct::new(PUNCTS[rng.below(PUNCTS.len())], spacing).into()
+        }
+        2 => Literal::u8_unsuffixed(rng.below(4) as u8).into(),
+        _ => {
+            let delimiter = match rng.below(3) {
+                0 => Delimiter::Brace,
+                1 => Delimiter::Bracket,
+                _ => Delimiter::Parenthesis,
+            };
+            Group::new(delimiter, random_stream(rng, depth - 1)).into()
+        }
+    }
+}
+
+fn random_stream(rng: &mut Rng, depth: usize) -> TokenStream {
+    (0..rng.below(8)).map(|_| random_tree(rng, depth)).collect()
+}
+
+/// Randomly drops, duplicates or replaces tokens of a (valid) token stream
+fn mutate(rng: &mut Rng, stream: TokenStream) -> TokenStream {
+    let mut out = vec![];
+
+    for tt in stream {
+        match rng.below(16) {
+            0 => {}
+            1 => {
+                out.push(tt.clone());
+                out.push(tt);
+            }
+            2 => out.push(random_tree(rng, 1)),
+            _ => out.push(match tt {
+                TokenTree::Group(g) => {
+                    let mut group = Group::new(g.delimiter(), mutate(rng, g.stream()));
+                    group.set_span(g.span());
+                    group.into()
+                }
+                tt => tt,
+            }),
+        }
+    }
+
+    out.into_iter().collect()
+}
+
+fn seeds() -> [TokenStream; 2] {
+    [app(), overflow()]
+}
+
+fn app() -> TokenStream {
+    quote!(
+        mod app {
+            use foo::bar;
+
+            #[shared]
+            struct Shared {
+                #[lock_free]
+                x: u32,
+                y: u32,
+            }
+
+            #[local]
+            struct Local {
+                z: u32,
+            }
+
+            #[monotonic(binds = SysTick, default = true)]
+            type Mono = Systick;
+
+            #[init(local = [a: u32 = 0])]
+            fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+            #[idle(shared = [&y], local = [z])]
+            fn idle(_: idle::Context) -> ! {}
+
+            #[task(binds = UART0, priority = 2, shared = [x, y])]
+            fn foo(_: foo::Context) {}
+
+            #[task(capacity = 2, shared = [x])]
+            fn bar(_: bar::Context, _: u32) {}
+
+            extern "Rust" {
+                #[task(priority = 3)]
+                fn baz(_: baz::Context);
+            }
+        }
+    )
+}
+
+/// Arithmetic at the edges of `i128` in `const` expressions
+fn overflow() -> TokenStream {
+    quote!(
+        mod app {
+            #[shared]
+            struct Shared {}
+
+            #[local]
+            struct Local {}
+
+            #[init]
+            fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+            #[task(priority = -(-170141183460469231731687303715884105727 - 1), capacity = MIN / -1)]
+            fn foo(_: foo::Context) {}
+
+            const MIN: u8 = -170141183460469231731687303715884105727 - 1;
+        }
+    )
+}
+
+fn settings(rng: &mut Rng) -> Settings {
+    Settings {
+        parse_binds: rng.below(2) == 0,
+        parse_extern_interrupt: rng.below(2) == 0,
+        optimize_priorities: rng.below(2) == 0,
+        parse_async_tasks: rng.below(2) == 0,
+        precise_send_types: rng.below(2) == 0,
+        lints: Lints {
+            unused_shared_resource: level(rng),
+            single_task_shared_resource: level(rng),
+            unused_dispatcher: level(rng),
+            unschedulable_task: level(rng),
+            incomplete_timing: level(rng),
+            unused_resource_access: level(rng),
+            undeclared_resource_access: level(rng),
+            never_spawned: level(rng),
+            inconsistent_cfg: level(rng),
+        },
+        device: if rng.below(2) == 0 {
+            Some(Device {
+                interrupts: vec!["foo".to_string(), "bar".to_string()],
+                exceptions: vec!["x".to_string(), "y".to_string()],
+                fixed_priority: vec!["y".to_string()],
+            })
+        } else {
+            None
+        },
+        cfg: if rng.below(2) == 0 {
+            let mut set = CfgSet::default();
+            set.insert_feature("foo");
+            Some(set)
+        } else {
+            None
+        },
+    }
+}
+
+fn level(rng: &mut Rng) -> Level {
+    match rng.below(3) {
+        0 => Level::Allow,
+        1 => Level::Warn,
+        _ => Level::Deny,
+    }
+}
+
+fn assert_no_panic(args: TokenStream, input: TokenStream, settings: Settings) {
+    let (args_s, input_s) = (args.to_string(), input.to_string());
+
+    if panic::catch_unwind(move || {
+        let _ = crate::parse2(args, input, settings);
+    })
+    .is_err()
+    {
+        panic!("`parse2` panicked\nargs: {}\ninput: {}", args_s, input_s);
+    }
+}
+
+#[test]
+fn random_token_streams() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+    for _ in 0..ITERATIONS {
+        let args = random_stream(&mut rng, 2);
+        let input = random_stream(&mut rng, 3);
+        let settings = settings(&mut rng);
+
+        assert_no_panic(args, input, settings);
+    }
+}
+
+#[test]
+fn random_app_bodies() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+
+    for _ in 0..ITERATIONS {
+        let body = random_stream(&mut rng, 3);
+        let input = quote!(mod app { #body });
+        let settings = settings(&mut rng);
+
+        assert_no_panic(quote!(), input, settings);
+    }
+}
+
+#[test]
+fn seeds_as_is() {
+    for seed in seeds() {
+        assert_no_panic(
+            quote!(dispatchers = [EXTI0, EXTI1]),
+            seed,
+            Settings::default(),
+        );
+    }
+}
+
+#[test]
+fn mutated_apps() {
+    let mut rng = Rng(0xdead_beef_cafe_f00d);
+
+    for _ in 0..ITERATIONS {
+        let args = mutate(&mut rng, quote!(dispatchers = [EXTI0, EXTI1]));
+        let seeds = seeds();
+        let seed = seeds[rng.below(seeds.len())].clone();
+        let input = mutate(&mut rng, seed);
+        let settings = settings(&mut rng);
+
+        assert_no_panic(args, input, settings);
+    }
+}
@@ -0,0 +1,97 @@
+use quote::quote;
+
+use crate::{
+    lint::{Level, Lint, Lints},
+    Settings,
+};
+
+fn lint(lints: Lints) -> Result<Vec<(Lint, String)>, String> {
+    crate::parse2(
+        quote!(dispatchers = [A, B]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    unused: u32,
+                    single: u32,
+                    both: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {
+                    foo::spawn().ok();
+                }
+
+                #[idle(shared = [both])]
+                fn idle(_: idle::Context) -> ! {}
+
+                #[task(shared = [single, &both])]
+                fn foo(_: foo::Context) {}
+            }
+        ),
+        Settings {
+            lints,
+            ..Settings::default()
+        },
+    )
+    .map(|(_app, analysis)| {
+        analysis
+            .warnings
+            .iter()
+            .map(|warning| (warning.lint, warning.message.clone()))
+            .collect()
+    })
+    .map_err(|e| e.to_string())
+}
+
+#[test]
+fn warn() {
+    assert_eq!(
+        lint(Lints::default()).unwrap(),
+        [
+            (
+                Lint::UnusedSharedResource,
+                "shared resource `unused` is never used".to_string()
+            ),
+            (
+                Lint::SingleTaskSharedResource,
+                "shared resource `single` is only used by `foo`, consider making it a local resource"
+                    .to_string()
+            ),
+            (
+                Lint::UnusedDispatcher,
+                "dispatcher `B` is never used".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn allow() {
+    let warnings = lint(Lints {
+        unused_shared_resource: Level::Allow,
+        unused_dispatcher: Level::Allow,
+        ..Lints::default()
+    })
+    .unwrap();
+
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].0, Lint::SingleTaskSharedResource);
+}
+
+#[test]
+fn deny() {
+    let error = lint(Lints {
+        unused_dispatcher: Level::Deny,
+        ..Lints::default()
+    })
+    .unwrap_err();
+
+    assert_eq!(
+        error,
+        "dispatcher `B` is never used (lint `unused_dispatcher` is set to deny)"
+    );
+}
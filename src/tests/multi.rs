@@ -0,0 +1,228 @@
+use std::collections::BTreeSet;
+
+use quote::{format_ident, quote};
+
+use crate::{
+    analyze::{Location, Ownership},
+    Settings,
+};
+
+#[test]
+fn placement() {
+    let (app, analysis) = crate::parse2(
+        quote!(cores = 2, dispatchers = [A, B, C]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    x: u32,
+                    y: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init(core = 1)]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle(core = 1, shared = [&y])]
+                fn idle(_: idle::Context) -> ! {}
+
+                #[task(capacity = 2, priority = 2, shared = [&x])]
+                fn foo(_: foo::Context, _: u32) {}
+
+                #[task(core = 1, shared = [&x, y])]
+                fn bar(_: bar::Context) {}
+
+                #[task(core = 1, priority = 2)]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    assert_eq!(app.args.cores, 2);
+    assert_eq!(app.init.args.core, 1);
+    assert_eq!(app.idle.as_ref().unwrap().args.core, 1);
+
+    // One channel per priority level of each core
+    assert_eq!(analysis.channels[&0][&2].capacity, 2);
+    assert_eq!(analysis.channels[&1].keys().collect::<Vec<_>>(), [&1, &2]);
+
+    // Dispatchers are assigned core by core, starting from the highest priority
+    let dispatchers = analysis
+        .dispatchers
+        .iter()
+        .flat_map(|(core, dispatchers)| {
+            dispatchers
+                .iter()
+                .map(move |(priority, interrupt)| (*core, *priority, interrupt.to_string()))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        dispatchers,
+        vec![
+            (0, 2, "A".to_string()),
+            (1, 1, "C".to_string()),
+            (1, 2, "B".to_string()),
+        ]
+    );
+
+    // `x` crosses cores, `y` doesn't
+    let (x, y) = (
+        app.shared_resources.get_index(0).unwrap().0,
+        app.shared_resources.get_index(1).unwrap().0,
+    );
+    assert_eq!(
+        analysis.locations[x],
+        Location::Shared {
+            cores: [0, 1].iter().cloned().collect::<BTreeSet<_>>()
+        }
+    );
+    assert_eq!(analysis.locations[y], Location::Owned { core: 1 });
+
+    // `x` is only read but from two cores so it must be `Sync`
+    assert_eq!(analysis.sync_types.len(), 1);
+}
+
+#[test]
+fn core_out_of_range() {
+    let result = crate::parse2(
+        quote!(cores = 2),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(binds = UART0, core = 2)]
+                fn foo(_: foo::Context) {}
+            }
+        ),
+        Settings {
+            parse_binds: true,
+            ..Settings::default()
+        },
+    );
+
+    let error = result.err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "core 2 doesn't exist, this application has 2 core(s) (`cores = 2`)"
+    );
+}
+
+#[test]
+fn lock_free_across_cores() {
+    let result = crate::parse2(
+        quote!(cores = 2),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    #[lock_free]
+                    x: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(binds = UART0, shared = [x])]
+                fn foo(_: foo::Context) {}
+
+                #[task(binds = UART1, core = 1, shared = [x])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings {
+            parse_binds: true,
+            ..Settings::default()
+        },
+    );
+
+    let error = result.err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Lock free shared resource \"x\" is used by tasks on different cores"
+    );
+}
+
+#[test]
+fn optimize_priorities_per_core() {
+    let (app, _analysis) = crate::parse2(
+        quote!(cores = 2, dispatchers = [A, B]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(priority = 3)]
+                fn foo(_: foo::Context) {}
+
+                #[task(core = 1, priority = 5)]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings {
+            optimize_priorities: true,
+            ..Settings::default()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(app.software_tasks[0].args.priority, 1);
+    assert_eq!(app.software_tasks[1].args.priority, 1);
+}
+
+#[test]
+fn same_priority_across_cores() {
+    // `foo` and `bar` run in parallel, they contend for `x` even at the same priority
+    let (_app, analysis) = crate::parse2(
+        quote!(cores = 2, dispatchers = [A, B]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    x: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [x])]
+                fn foo(_: foo::Context) {}
+
+                #[task(core = 1, shared = [x])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        analysis.ownerships[&format_ident!("x")],
+        Ownership::Contended {
+            ceiling: 1,
+            read_ceiling: 1
+        }
+    );
+}
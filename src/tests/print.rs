@@ -0,0 +1,176 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::ItemMod;
+
+use crate::{ast::App, Settings, P};
+
+fn settings() -> Settings {
+    Settings {
+        parse_binds: true,
+        parse_async_tasks: true,
+        ..Settings::default()
+    }
+}
+
+/// Parses the output of the printer
+fn reparse(printed: TokenStream) -> P<App> {
+    let mut item: ItemMod = syn::parse2(printed).unwrap();
+    let args: TokenStream = item.attrs.remove(0).parse_args().unwrap();
+
+    let (app, _analysis) = crate::parse2(args, item.into_token_stream(), settings()).unwrap();
+    app
+}
+
+#[test]
+fn round_trip() {
+    let (app, _analysis) = crate::parse2(
+        quote!(
+            device = lm3s6965,
+            peripherals = false,
+            dispatchers = [
+                #[link_section = ".data"]
+                A,
+                B
+            ]
+        ),
+        quote!(
+            mod app {
+                use core::fmt::Write;
+
+                #[shared]
+                struct Shared {
+                    /// Docs
+                    #[lock_free]
+                    x: u32,
+                    #[cfg(feature = "y")]
+                    y: [u8; 4],
+                }
+
+                #[local]
+                struct Local {
+                    l: u32,
+                }
+
+                #[monotonic(binds = SysTick, priority = 3, default = true)]
+                type Mono = Systick<100>;
+
+                #[init(local = [#[link_section = ".data"] a: u32 = 0])]
+                fn init(cx: init::Context) -> (Shared, Local, init::Monotonics) {
+                    let x = 0;
+                    (Shared { x, y: [0; 4] }, Local { l: 1 }, init::Monotonics())
+                }
+
+                #[idle(shared = [#[cfg(feature = "y")] &y], local = [l])]
+                async fn idle(_: idle::Context) -> ! {
+                    loop {}
+                }
+
+                #[task(binds = UART0, priority = 2, threshold = 3, wcet = 1500ns, period = 1ms, shared = [x])]
+                fn foo(_: foo::Context) {}
+
+                #[inline(always)]
+                #[task(capacity = 4, priority = 2, shared = [x, &y], local = [b: i8 = -1])]
+                async fn bar(_: bar::Context, a: u32, (b, c): (u8, u8)) {}
+
+                extern "Rust" {
+                    #[task(priority = 2)]
+                    fn baz(_: baz::Context, _: u32);
+                }
+
+                fn helper() {}
+
+                struct Other;
+            }
+        ),
+        settings(),
+    )
+    .unwrap();
+
+    let printed = app.to_token_stream();
+    let reparsed = reparse(printed.clone());
+
+    assert_eq!(printed.to_string(), reparsed.to_token_stream().to_string());
+
+    assert_eq!(
+        reparsed
+            .args
+            .device
+            .as_ref()
+            .unwrap()
+            .to_token_stream()
+            .to_string(),
+        "lm3s6965"
+    );
+    assert!(!reparsed.args.peripherals);
+    assert_eq!(reparsed.args.extern_interrupts.len(), 2);
+    assert!(reparsed.shared_resources[0].properties.lock_free);
+    assert_eq!(reparsed.shared_resources[1].cfgs.len(), 1);
+    assert_eq!(reparsed.monotonics[0].args.priority, Some(3));
+    let idle = reparsed.idle.as_ref().unwrap();
+    assert!(idle.is_async);
+    assert_eq!(idle.args.shared_resources[0].cfgs.len(), 1);
+    assert!(idle.args.shared_resources[0].access.is_shared());
+    assert_eq!(reparsed.hardware_tasks[0].args.priority, 2);
+    assert_eq!(
+        reparsed.hardware_tasks[0].args.timing.wcet,
+        Some(core::time::Duration::from_nanos(1500))
+    );
+    assert_eq!(reparsed.software_tasks[0].args.capacity, 4);
+    assert_eq!(reparsed.software_tasks[0].inputs.len(), 2);
+    assert!(reparsed.software_tasks[1].is_extern);
+    // `helper`, `Other` and the `Mono` alias
+    assert_eq!(reparsed.user_code.len(), 3);
+    assert_eq!(reparsed.user_imports.len(), 1);
+}
+
+#[test]
+fn canonicalize() {
+    let (app, _analysis) = crate::parse2(
+        quote!(dispatchers = [B, A]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    c: u32,
+                    a: u32,
+                    b: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [c, &b, a])]
+                fn foo(_: foo::Context) {}
+            }
+        ),
+        settings(),
+    )
+    .unwrap();
+
+    let printed = app.to_canonical_tokens().to_string();
+    let expected = quote!(
+        #[app(dispatchers = [B, A])]
+        mod app {
+            #[shared]
+            struct Shared {
+                a: u32,
+                b: u32,
+                c: u32,
+            }
+
+            #[local]
+            struct Local {}
+
+            #[init]
+            fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+            #[task(shared = [a, &b, c])]
+            fn foo(_: foo::Context) {}
+        }
+    );
+
+    assert_eq!(printed, expected.to_string());
+}
@@ -0,0 +1,149 @@
+use core::time::Duration;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{
+    lint::{Level, Lint, Lints},
+    Settings,
+};
+
+fn app(lo: TokenStream, lints: Lints) -> Result<crate::P<crate::analyze::Analysis>, String> {
+    crate::parse2(
+        quote!(dispatchers = [A, B, C]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    r: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {
+                    hi::spawn().ok();
+                    mid::spawn().ok();
+                    lo::spawn().ok();
+                }
+
+                #[task(priority = 3, wcet = 1ms, period = 4ms, shared = [r])]
+                fn hi(_: hi::Context) {}
+
+                #[task(priority = 2, wcet = 2ms, period = 10ms)]
+                fn mid(_: mid::Context) {}
+
+                #[task(#lo, shared = [r])]
+                fn lo(_: lo::Context) {}
+            }
+        ),
+        Settings {
+            lints,
+            ..Settings::default()
+        },
+    )
+    .map(|(_app, analysis)| analysis)
+    .map_err(|e| e.to_string())
+}
+
+fn ms(ms: u64) -> Option<Duration> {
+    Some(Duration::from_millis(ms))
+}
+
+#[test]
+fn response_times() {
+    let analysis = app(quote!(wcet = 3ms, period = 20ms), Lints::default()).unwrap();
+
+    let response_times = analysis
+        .response_times
+        .iter()
+        .map(|(name, response_time)| {
+            (
+                name.to_string(),
+                response_time.blocking,
+                response_time.response_time,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // `lo` holds `r` (ceiling 3) so it can block both `hi` and `mid`
+    assert_eq!(
+        response_times,
+        [
+            ("hi".to_string(), Duration::from_millis(3), ms(4)),
+            ("mid".to_string(), Duration::from_millis(3), ms(7)),
+            ("lo".to_string(), Duration::ZERO, ms(7)),
+        ]
+    );
+    assert!(analysis.warnings.is_empty());
+}
+
+#[test]
+fn unschedulable() {
+    let lo = quote!(wcet = 3ms, period = 20ms, deadline = 5ms);
+
+    let analysis = app(lo.clone(), Lints::default()).unwrap();
+    assert!(!analysis.response_times[2].is_schedulable());
+    assert_eq!(analysis.warnings.len(), 1);
+    assert_eq!(analysis.warnings[0].lint, Lint::UnschedulableTask);
+    assert_eq!(
+        analysis.warnings[0].message,
+        "the worst-case response time of `lo` exceeds its deadline of 5ms (wcet: 3ms, blocking: 0s)"
+    );
+
+    let lints = Lints {
+        unschedulable_task: Level::Deny,
+        ..Lints::default()
+    };
+    assert!(app(lo, lints).is_err());
+}
+
+#[test]
+fn incomplete_timing() {
+    // `lo` can block `hi` and `mid` but has no `wcet`
+    let analysis = app(quote!(priority = 1), Lints::default()).unwrap();
+
+    let warnings = analysis
+        .warnings
+        .iter()
+        .map(|warning| (warning.lint, warning.message.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        warnings,
+        [
+            (
+                Lint::IncompleteTiming,
+                "the response time of `hi` can't be computed, `lo` can block or preempt it but \
+                 lack(s) a `wcet` or `period`"
+                    .to_string()
+            ),
+            (
+                Lint::IncompleteTiming,
+                "the response time of `mid` can't be computed, `lo` can block or preempt it but \
+                 lack(s) a `wcet` or `period`"
+                    .to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn deadline_after_period() {
+    // The analysis assumes a job ends before the next one is released
+    assert_eq!(
+        app(
+            quote!(wcet = 1ms, period = 1ms, deadline = 5ms),
+            Lints::default()
+        )
+        .err()
+        .unwrap(),
+        "the `deadline` (5ms) can't be longer than the `period` (1ms)"
+    );
+
+    assert!(app(
+        quote!(wcet = 1ms, period = 20ms, deadline = 20ms),
+        Lints::default()
+    )
+    .is_ok());
+}
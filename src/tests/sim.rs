@@ -0,0 +1,289 @@
+use core::time::Duration;
+
+use quote::{format_ident, quote};
+
+use crate::{
+    sim::{self, Arrival, Error, Model, Step, TaskModel},
+    Settings,
+};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+fn simulate(model: Model) -> Result<Vec<String>, Error> {
+    let (app, analysis) = crate::parse2(
+        quote!(dispatchers = [A, B]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    r: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(binds = UART0, priority = 3, shared = [r])]
+                fn hi(_: hi::Context) {}
+
+                #[task(priority = 2)]
+                fn mid(_: mid::Context) {}
+
+                #[task(deadline = 3ms, shared = [r])]
+                fn lo(_: lo::Context) {}
+            }
+        ),
+        Settings {
+            parse_binds: true,
+            ..Settings::default()
+        },
+    )
+    .unwrap();
+
+    let trace = sim::simulate(&app, &analysis, &model, ms(100))?;
+
+    Ok(trace
+        .events
+        .iter()
+        .map(|event| {
+            event
+                .to_string()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect())
+}
+
+#[test]
+fn srp() {
+    let r = format_ident!("r");
+
+    let mut model = Model::new();
+    model.insert(
+        format_ident!("lo"),
+        TaskModel {
+            // The second release is dropped, the capacity of `lo` is 1
+            arrival: Arrival::At(vec![ms(0), ms(0)]),
+            steps: vec![
+                Step::Lock(r.clone()),
+                Step::Run(ms(2)),
+                Step::Unlock(r.clone()),
+                Step::Run(ms(2)),
+            ],
+        },
+    );
+    model.insert(
+        format_ident!("mid"),
+        TaskModel {
+            arrival: Arrival::At(vec![ms(1)]),
+            steps: vec![Step::Run(ms(1))],
+        },
+    );
+    model.insert(
+        format_ident!("hi"),
+        TaskModel {
+            arrival: Arrival::At(vec![ms(1)]),
+            steps: vec![Step::Lock(r.clone()), Step::Run(ms(1)), Step::Unlock(r)],
+        },
+    );
+
+    assert_eq!(
+        simulate(model).unwrap(),
+        [
+            "0s core 0 lo release",
+            "0s core 0 lo dropped",
+            "0s core 0 lo start",
+            "0s core 0 lo lock r",
+            "1ms core 0 mid release",
+            "1ms core 0 hi release",
+            // `r` has a ceiling of 3, `hi` and `mid` can't start before `lo` unlocks it
+            "2ms core 0 lo unlock r",
+            "2ms core 0 lo preempt",
+            "2ms core 0 hi start",
+            "2ms core 0 hi lock r",
+            "3ms core 0 hi unlock r",
+            "3ms core 0 hi finish",
+            "3ms core 0 mid start",
+            "4ms core 0 mid finish",
+            "4ms core 0 lo resume",
+            "6ms core 0 lo finish",
+            "6ms core 0 lo deadline miss",
+        ]
+    );
+}
+
+#[test]
+fn spawn() {
+    let mut model = Model::new();
+    model.insert(
+        format_ident!("hi"),
+        TaskModel {
+            arrival: Arrival::Periodic {
+                offset: ms(0),
+                period: ms(50),
+            },
+            steps: vec![
+                Step::Spawn(format_ident!("mid")),
+                Step::Run(ms(1)),
+                Step::Spawn(format_ident!("mid")),
+            ],
+        },
+    );
+
+    assert_eq!(
+        simulate(model).unwrap(),
+        [
+            "0s core 0 hi release",
+            "0s core 0 hi start",
+            "0s core 0 mid release",
+            "1ms core 0 mid dropped",
+            "1ms core 0 hi finish",
+            "1ms core 0 mid start",
+            "1ms core 0 mid finish",
+            "50ms core 0 hi release",
+            "50ms core 0 hi start",
+            "50ms core 0 mid release",
+            "51ms core 0 mid dropped",
+            "51ms core 0 hi finish",
+            "51ms core 0 mid start",
+            "51ms core 0 mid finish",
+            "100ms core 0 hi release",
+            "100ms core 0 hi start",
+            "100ms core 0 mid release",
+        ]
+    );
+}
+
+#[test]
+fn invalid_model() {
+    let mut model = Model::new();
+    model.insert(
+        format_ident!("mid"),
+        TaskModel {
+            arrival: Arrival::Spawned,
+            steps: vec![Step::Lock(format_ident!("r"))],
+        },
+    );
+
+    let error = simulate(model).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "`mid` locks `r` but it's not in its `shared` resources"
+    );
+
+    let mut model = Model::new();
+    model.insert(
+        format_ident!("hi"),
+        TaskModel {
+            arrival: Arrival::Spawned,
+            steps: vec![Step::Spawn(format_ident!("hi"))],
+        },
+    );
+
+    assert_eq!(
+        simulate(model).unwrap_err(),
+        Error::NotSoftwareTask {
+            task: format_ident!("hi"),
+            spawnee: format_ident!("hi"),
+        }
+    );
+}
+
+#[test]
+fn instant_spawn_cycle() {
+    let mut model = Model::new();
+    model.insert(
+        format_ident!("hi"),
+        TaskModel {
+            arrival: Arrival::At(vec![ms(0)]),
+            steps: vec![Step::Spawn(format_ident!("mid"))],
+        },
+    );
+    model.insert(
+        format_ident!("mid"),
+        TaskModel {
+            arrival: Arrival::Spawned,
+            steps: vec![Step::Run(ms(0)), Step::Spawn(format_ident!("lo"))],
+        },
+    );
+    model.insert(
+        format_ident!("lo"),
+        TaskModel {
+            arrival: Arrival::Spawned,
+            steps: vec![Step::Spawn(format_ident!("mid"))],
+        },
+    );
+
+    let error = simulate(model.clone()).unwrap_err();
+    assert_eq!(
+        error,
+        Error::InstantSpawnCycle(vec![
+            format_ident!("mid"),
+            format_ident!("lo"),
+            format_ident!("mid")
+        ])
+    );
+    assert_eq!(
+        error.to_string(),
+        "`mid` -> `lo` -> `mid` spawn each other without running for any time"
+    );
+
+    // Time advances once `lo` runs, the cycle stops at the end of the simulation
+    model[&format_ident!("lo")]
+        .steps
+        .insert(0, Step::Run(ms(10)));
+    let events = simulate(model).unwrap();
+    assert_eq!(
+        events.last().map(|event| &**event),
+        Some("100ms core 0 lo start")
+    );
+}
+
+#[test]
+fn task_variants() {
+    let (app, analysis) = crate::parse2(
+        quote!(dispatchers = [A, B]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[cfg(feature = "a")]
+                #[task]
+                fn foo(_: foo::Context) {}
+
+                #[cfg(not(feature = "a"))]
+                #[task(priority = 2)]
+                fn foo(_: foo::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    // The model can't tell which variant of `foo` it describes
+    let mut model = Model::new();
+    model.insert(
+        format_ident!("foo"),
+        TaskModel {
+            arrival: Arrival::At(vec![ms(1)]),
+            steps: vec![Step::Run(ms(1))],
+        },
+    );
+
+    assert_eq!(
+        sim::simulate(&app, &analysis, &model, ms(10)).unwrap_err(),
+        Error::AmbiguousTask(format_ident!("foo"))
+    );
+}
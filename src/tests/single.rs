@@ -1,11 +1,11 @@
-use crate::{analyze::Ownership, Settings};
+use crate::{analyze::Ownership, ast::Access, Settings};
 use quote::quote;
 
 #[test]
 fn unused_task() {
     // this shouldn't crash the analysis pass
     crate::parse2(
-        quote!(),
+        quote!(dispatchers = [A]),
         quote!(
             mod app {
                 #[shared]
@@ -29,7 +29,7 @@ fn unused_task() {
 #[test]
 fn shared_resource_owned() {
     let (_app, analysis) = crate::parse2(
-        quote!(),
+        quote!(dispatchers = [A]),
         quote!(
             mod app {
                 #[shared]
@@ -59,7 +59,7 @@ fn shared_resource_owned() {
 #[test]
 fn shared_resource_coowned() {
     let (_app, analysis) = crate::parse2(
-        quote!(),
+        quote!(dispatchers = [A]),
         quote!(
             mod app {
                 #[shared]
@@ -92,7 +92,7 @@ fn shared_resource_coowned() {
 #[test]
 fn shared_resource_contended() {
     let (_app, analysis) = crate::parse2(
-        quote!(),
+        quote!(dispatchers = [A, B]),
         quote!(
             mod app {
                 #[shared]
@@ -119,7 +119,13 @@ fn shared_resource_contended() {
 
     let (res, ownership) = analysis.ownerships.iter().next().unwrap();
     assert_eq!(res.to_string(), "x");
-    assert_eq!(*ownership, Ownership::Contended { ceiling: 2 });
+    assert_eq!(
+        *ownership,
+        Ownership::Contended {
+            ceiling: 2,
+            read_ceiling: 2
+        }
+    );
 }
 
 #[test]
@@ -157,7 +163,7 @@ fn no_send_late_shared_resources_idle() {
 fn send_spawn() {
     // message passing between different priority tasks needs a `Send` bound
     let (_app, analysis) = crate::parse2(
-        quote!(),
+        quote!(dispatchers = [A, B]),
         quote!(
             mod app {
                 #[shared]
@@ -180,7 +186,7 @@ fn send_spawn() {
     )
     .unwrap();
 
-    let ty = analysis.send_types.iter().next().unwrap();
+    let ty = analysis.send_types.keys().next().unwrap();
     assert_eq!(quote!(#ty).to_string(), "X");
 }
 
@@ -188,7 +194,7 @@ fn send_spawn() {
 fn send_shared_resource() {
     // shared resources used by tasks must be `Send`
     let (_app, analysis) = crate::parse2(
-        quote!(),
+        quote!(dispatchers = [A]),
         quote!(
             mod app {
                 #[shared]
@@ -210,7 +216,7 @@ fn send_shared_resource() {
     )
     .unwrap();
 
-    let ty = analysis.send_types.iter().next().unwrap();
+    let ty = analysis.send_types.keys().next().unwrap();
     assert_eq!(quote!(#ty).to_string(), "X");
 }
 
@@ -218,7 +224,7 @@ fn send_shared_resource() {
 fn send_local_resource() {
     // local resources used by tasks from the Local struct must be `Send`
     let (_app, analysis) = crate::parse2(
-        quote!(),
+        quote!(dispatchers = [A]),
         quote!(
             mod app {
                 #[shared]
@@ -240,7 +246,7 @@ fn send_local_resource() {
     )
     .unwrap();
 
-    let ty = analysis.send_types.iter().next().unwrap();
+    let ty = analysis.send_types.keys().next().unwrap();
     assert_eq!(quote!(#ty).to_string(), "X");
 }
 
@@ -248,7 +254,7 @@ fn send_local_resource() {
 fn send_shared_with_init() {
     // resources shared with `init` must be `Send`
     let (_app, analysis) = crate::parse2(
-        quote!(),
+        quote!(dispatchers = [A]),
         quote!(
             mod app {
                 #[shared]
@@ -270,7 +276,7 @@ fn send_shared_with_init() {
     )
     .unwrap();
 
-    let ty = analysis.send_types.iter().next().unwrap();
+    let ty = analysis.send_types.keys().next().unwrap();
     assert_eq!(quote!(#ty).to_string(), "i32");
 }
 
@@ -278,7 +284,7 @@ fn send_shared_with_init() {
 fn not_sync() {
     // `static` resources shared between same priority tasks don't need a `Sync` bound
     let (_app, analysis) = crate::parse2(
-        quote!(),
+        quote!(dispatchers = [A]),
         quote!(
             mod app {
                 #[shared]
@@ -310,7 +316,7 @@ fn not_sync() {
 fn sync() {
     // `static` resources shared between different priority tasks need to be `Sync`
     let (_app, analysis) = crate::parse2(
-        quote!(),
+        quote!(dispatchers = [A, B]),
         quote!(
             mod app {
                 #[shared]
@@ -343,7 +349,7 @@ fn sync() {
 fn not_sync2() {
     // `static` resources shared between same priority tasks do not need to be `Sync`
     let (_app, analysis) = crate::parse2(
-        quote!(),
+        quote!(dispatchers = [A]),
         quote!(
             mod app {
                 #[shared]
@@ -376,7 +382,7 @@ fn not_sync3() {
     // `static` resources between different priority tasks do not need to be `Sync`, protected by
     // the mutex
     let (_app, analysis) = crate::parse2(
-        quote!(),
+        quote!(dispatchers = [A, B]),
         quote!(
             mod app {
                 #[shared]
@@ -432,3 +438,364 @@ fn late_resources() {
     let late = &app.shared_resources;
     assert_eq!(late.len(), 1);
 }
+
+#[test]
+fn async_task_not_parsed_by_default() {
+    // `async` software tasks are only accepted if `Settings::parse_async_tasks` is set
+    let result = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task]
+                async fn foo(_: foo::Context) {}
+            }
+        ),
+        Settings::default(),
+    );
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn async_tasks() {
+    let settings = Settings {
+        parse_async_tasks: true,
+        ..Settings::default()
+    };
+
+    let (app, analysis) = crate::parse2(
+        quote!(dispatchers = [A, B, C]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[idle]
+                async fn idle(_: idle::Context) -> ! {}
+
+                #[task]
+                async fn foo(_: foo::Context) {}
+
+                #[task(priority = 2)]
+                fn bar(_: bar::Context) {}
+
+                #[task(priority = 3)]
+                async fn baz(_: baz::Context, _: u32) {}
+            }
+        ),
+        settings,
+    )
+    .unwrap();
+
+    assert!(app.idle.as_ref().unwrap().is_async);
+    assert!(app.software_tasks.values().next().unwrap().is_async);
+    assert_eq!(
+        analysis.async_priorities[&0]
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>(),
+        vec![1, 3]
+    );
+}
+
+#[test]
+fn dispatchers() {
+    // Dispatchers are assigned in declaration order, starting from the highest priority
+    let (_app, analysis) = crate::parse2(
+        quote!(dispatchers = [A, B, C]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 3)]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let dispatchers = analysis.dispatchers[&0]
+        .iter()
+        .map(|(priority, interrupt)| (*priority, interrupt.to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        dispatchers,
+        vec![(1, "B".to_string()), (3, "A".to_string())]
+    );
+}
+
+#[test]
+fn shared_resource_read_write() {
+    // `&x` readers only need to lock out the `x` writers
+    let (_app, analysis) = crate::parse2(
+        quote!(dispatchers = [A, B, C]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    x: i32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [&x])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, shared = [x])]
+                fn bar(_: bar::Context) {}
+
+                #[task(priority = 3, shared = [&x])]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let ownership = analysis.ownerships.values().next().unwrap();
+    assert_eq!(
+        *ownership,
+        Ownership::Contended {
+            ceiling: 3,
+            read_ceiling: 2
+        }
+    );
+
+    assert!(ownership.needs_lock(1, Access::Shared));
+    assert!(!ownership.needs_lock(3, Access::Shared));
+    assert!(ownership.needs_lock(2, Access::Exclusive));
+
+    // readers at different priorities may run concurrently
+    let ty = analysis.sync_types.iter().next().unwrap();
+    assert_eq!(quote!(#ty).to_string(), "i32");
+}
+
+#[test]
+fn shared_resource_read_only() {
+    // Without writers, readers never need a lock
+    let (_app, analysis) = crate::parse2(
+        quote!(dispatchers = [A, B]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    x: i32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [&x])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, shared = [&x])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let ownership = analysis.ownerships.values().next().unwrap();
+    assert_eq!(*ownership, Ownership::ReadOnly { ceiling: 2 });
+    assert!(!ownership.needs_lock(1, Access::Shared));
+
+    // readers at different priorities may run concurrently
+    let ty = analysis.sync_types.iter().next().unwrap();
+    assert_eq!(quote!(#ty).to_string(), "i32");
+}
+
+#[test]
+fn lock_free_read_only() {
+    // A read-only resource can be `#[lock_free]` whatever the priorities and cores of its readers
+    let (_app, analysis) = crate::parse2(
+        quote!(cores = 2, dispatchers = [A, B]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    #[lock_free]
+                    calibration: [u16; 8],
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [&calibration])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, core = 1, shared = [&calibration])]
+                fn bar(_: bar::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        analysis.ownerships.values().next(),
+        Some(&Ownership::ReadOnly { ceiling: 2 })
+    );
+    assert_eq!(analysis.sync_types.len(), 1);
+}
+
+#[test]
+fn atomic_resource() {
+    // `#[atomic]` resources are never locked, whatever the priorities and cores of their users
+    let (_app, analysis) = crate::parse2(
+        quote!(cores = 2, dispatchers = [A, B, C]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    #[atomic]
+                    counter: AtomicU32,
+                    x: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(shared = [&counter, x])]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = 2, shared = [&counter])]
+                fn bar(_: bar::Context) {}
+
+                #[task(core = 1, shared = [&counter])]
+                fn baz(_: baz::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let names =
+        |set: &crate::Set<syn::Ident>| set.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+    assert_eq!(names(&analysis.atomic_resources), ["counter"]);
+    assert_eq!(names(&analysis.shared_resources), ["counter", "x"]);
+
+    // `x` is the only resource with a ceiling
+    assert_eq!(analysis.ownerships.len(), 1);
+    assert_eq!(
+        analysis.ownerships.values().next(),
+        Some(&Ownership::Owned { priority: 1 })
+    );
+
+    let sync_types = analysis
+        .sync_types
+        .iter()
+        .map(|ty| quote!(#ty).to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(sync_types, ["AtomicU32"]);
+}
+
+#[test]
+fn const_priorities() {
+    // `const` items can be used before they are declared
+    let (app, analysis) = crate::parse2(
+        quote!(dispatchers = [A, B]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[monotonic(binds = SysTick, priority = (HIGH + 1) * 2)]
+                type Mono = Systick;
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(priority = HIGH, capacity = 1 << 2)]
+                fn foo(_: foo::Context) {}
+
+                #[task(priority = HIGH - BASE % 2)]
+                fn bar(_: bar::Context) {}
+
+                const BASE: u8 = 1;
+                const HIGH: u8 = BASE + 2;
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    assert_eq!(app.monotonics[0].args.priority, Some(8));
+    assert_eq!(app.software_tasks[0].args.priority, 3);
+    assert_eq!(app.software_tasks[0].args.capacity, 4);
+    assert_eq!(app.software_tasks[1].args.priority, 2);
+    assert_eq!(analysis.channels[&0].keys().collect::<Vec<_>>(), [&2, &3]);
+}
+
+#[test]
+fn const_overflow() {
+    // `-i128::MIN` doesn't fit in an `i128`
+    let error = crate::parse2(
+        quote!(),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(priority = -(-170141183460469231731687303715884105727 - 1))]
+                fn foo(_: foo::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .err()
+    .unwrap();
+
+    assert_eq!(
+        error.to_string(),
+        "this operation overflows or divides by zero"
+    );
+}
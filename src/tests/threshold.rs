@@ -0,0 +1,178 @@
+use core::time::Duration;
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::{
+    analyze::{Analysis, Ownership},
+    ast::{Access, App},
+    sim::{self, Arrival, Model, Step, TaskModel},
+    Settings, P,
+};
+
+fn app(lo: TokenStream, settings: Settings) -> (P<App>, P<Analysis>) {
+    crate::parse2(
+        quote!(dispatchers = [A, B, C]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {
+                    r: u32,
+                }
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(priority = 4, shared = [r])]
+                fn hi(_: hi::Context) {}
+
+                #[task(#lo, shared = [r])]
+                fn lo(_: lo::Context) {}
+            }
+        ),
+        settings,
+    )
+    .unwrap()
+}
+
+#[test]
+fn ownership() {
+    // `lo` can be preempted by `hi`
+    let (_, analysis) = app(quote!(priority = 2, threshold = 3), Settings::default());
+    let ownership = analysis.ownerships[0];
+    assert_eq!(
+        ownership,
+        Ownership::Contended {
+            ceiling: 4,
+            read_ceiling: 4
+        }
+    );
+    assert!(ownership.needs_lock(3, Access::Exclusive));
+
+    // `hi` can't preempt `lo` once it runs, `r` is accessed in turns
+    let (_, analysis) = app(quote!(priority = 2, threshold = 4), Settings::default());
+    assert_eq!(analysis.ownerships[0], Ownership::CoOwned { priority: 4 });
+}
+
+#[test]
+fn needs_lock() {
+    let ownership = Ownership::Contended {
+        ceiling: 3,
+        read_ceiling: 2,
+    };
+
+    assert!(ownership.needs_lock(1, Access::Exclusive));
+    assert!(!ownership.needs_lock(2, Access::Shared));
+    assert!(!ownership.needs_lock(4, Access::Exclusive));
+}
+
+#[test]
+fn optimize_priorities() {
+    let (app, _) = app(
+        quote!(priority = 2, threshold = 3),
+        Settings {
+            optimize_priorities: true,
+            ..Settings::default()
+        },
+    );
+
+    // Priorities 2 and 4 become 1 and 2; threshold 3 keeps out the old priority 2 but not 4
+    let lo = &app
+        .software_tasks
+        .variants(&format_ident!("lo"))
+        .next()
+        .unwrap()
+        .args;
+    assert_eq!((lo.priority, lo.threshold), (1, Some(1)));
+    assert_eq!(
+        app.software_tasks
+            .variants(&format_ident!("hi"))
+            .next()
+            .unwrap()
+            .args
+            .priority,
+        2
+    );
+}
+
+#[test]
+fn blocking() {
+    // `hi` can't preempt `lo`, even though they share no resource, so it may wait for all of it
+    let (_, analysis) = crate::parse2(
+        quote!(dispatchers = [A, B]),
+        quote!(
+            mod app {
+                #[shared]
+                struct Shared {}
+
+                #[local]
+                struct Local {}
+
+                #[init]
+                fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+                #[task(priority = 2, wcet = 1ms, period = 10ms)]
+                fn hi(_: hi::Context) {}
+
+                #[task(threshold = 2, wcet = 3ms, period = 20ms)]
+                fn lo(_: lo::Context) {}
+            }
+        ),
+        Settings::default(),
+    )
+    .unwrap();
+
+    let hi = &analysis.response_times[&format_ident!("hi")];
+    assert_eq!(hi.blocking, Duration::from_millis(3));
+    assert_eq!(hi.response_time, Some(Duration::from_millis(4)));
+}
+
+#[test]
+fn simulation() {
+    let (app, analysis) = app(quote!(priority = 2, threshold = 4), Settings::default());
+
+    let mut model = Model::new();
+    model.insert(
+        format_ident!("lo"),
+        TaskModel {
+            arrival: Arrival::At(vec![Duration::ZERO]),
+            steps: vec![Step::Run(Duration::from_millis(2))],
+        },
+    );
+    model.insert(
+        format_ident!("hi"),
+        TaskModel {
+            arrival: Arrival::At(vec![Duration::from_millis(1)]),
+            steps: vec![Step::Run(Duration::from_millis(1))],
+        },
+    );
+
+    let trace = sim::simulate(&app, &analysis, &model, Duration::from_millis(10)).unwrap();
+    let events = trace
+        .events
+        .iter()
+        .map(|event| {
+            event
+                .to_string()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>();
+
+    // `hi` waits for `lo` to finish instead of preempting it
+    assert_eq!(
+        events,
+        [
+            "0s core 0 lo release",
+            "0s core 0 lo start",
+            "1ms core 0 hi release",
+            "2ms core 0 lo finish",
+            "2ms core 0 hi start",
+            "3ms core 0 hi finish",
+        ]
+    );
+}
@@ -0,0 +1,21 @@
+#![no_main]
+
+#[mock::app(deny(inconsistent_cfg), dispatchers = [A])]
+mod app {
+    #[shared]
+    struct Shared {
+        #[cfg(feature = "radio")]
+        packets: u32,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {
+        foo::spawn().ok();
+    }
+
+    #[task(shared = [packets])]
+    fn foo(_: foo::Context) {}
+}
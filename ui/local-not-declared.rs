@@ -1,6 +1,6 @@
 #![no_main]
 
-#[mock::app]
+#[mock::app(dispatchers = [EXTI0])]
 mod app {
     #[shared]
     struct Shared {}
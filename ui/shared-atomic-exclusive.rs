@@ -0,0 +1,19 @@
+#![no_main]
+
+#[mock::app(dispatchers = [EXTI0])]
+mod app {
+    #[shared]
+    struct Shared {
+        #[atomic]
+        counter: core::sync::atomic::AtomicU32,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+    #[task(shared = [counter])]
+    fn foo(_: foo::Context) {}
+}
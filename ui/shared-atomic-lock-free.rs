@@ -0,0 +1,11 @@
+#![no_main]
+
+#[mock::app]
+mod app {
+    #[shared]
+    struct Shared {
+        #[atomic]
+        #[lock_free]
+        counter: core::sync::atomic::AtomicU32,
+    }
+}
@@ -0,0 +1,19 @@
+#![no_main]
+
+#[mock::app(dispatchers = [A])]
+mod app {
+    #[shared]
+    struct Shared {
+        a: u32,
+        b: u32,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+    #[task(shared = [& #[cfg(feature = "a")] a, & #[inline] b])]
+    fn foo(_: foo::Context) {}
+}
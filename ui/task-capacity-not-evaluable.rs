@@ -0,0 +1,7 @@
+#![no_main]
+
+#[mock::app]
+mod app {
+    #[task(capacity = size_of::<u32>())]
+    fn foo(_: foo::Context) {}
+}
@@ -0,0 +1,16 @@
+#![no_main]
+
+#[mock::app(cores = 2, dispatchers = [A])]
+mod app {
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+    #[task(core = 2)]
+    fn foo(_: foo::Context) {}
+}
@@ -0,0 +1,7 @@
+#![no_main]
+
+#[mock::app]
+mod app {
+    #[task(wcet = 100us, period = 1ms, deadline = 5ms)]
+    fn foo(_: foo::Context) {}
+}
@@ -0,0 +1,7 @@
+#![no_main]
+
+#[mock::app]
+mod app {
+    #[task(core = 0, core = 1)]
+    fn foo(_: foo::Context) {}
+}
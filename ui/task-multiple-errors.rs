@@ -0,0 +1,22 @@
+#![no_main]
+
+#[mock::app(dispatchers = [A, A])]
+mod app {
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+    #[task(priority = 0, capacity = 1, capcity = 2)]
+    fn foo(_: foo::Context) {}
+
+    #[task(shared = [a, a], local = [b: u32])]
+    fn bar(_: bar::Context) {}
+
+    #[task]
+    pub fn baz(_: baz::Context) {}
+}
@@ -0,0 +1,22 @@
+#![no_main]
+
+#[mock::app(dispatchers = [EXTI0])]
+mod app {
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local, init::Monotonics) {}
+
+    #[task(priority = 1)]
+    fn foo(_: foo::Context) {}
+
+    #[task(priority = 2)]
+    fn bar(_: bar::Context) {}
+
+    #[task(priority = 3)]
+    fn baz(_: baz::Context) {}
+}
@@ -0,0 +1,13 @@
+#![no_main]
+
+#[mock::app]
+mod app {
+    #[task(priority = P)]
+    fn foo(_: foo::Context) {}
+
+    #[cfg(feature = "fast")]
+    const P: u8 = 2;
+
+    #[cfg(not(feature = "fast"))]
+    const P: u8 = 1;
+}
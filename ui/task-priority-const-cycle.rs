@@ -0,0 +1,10 @@
+#![no_main]
+
+#[mock::app]
+mod app {
+    #[task(priority = A)]
+    fn foo(_: foo::Context) {}
+
+    const A: u8 = B;
+    const B: u8 = A + 1;
+}
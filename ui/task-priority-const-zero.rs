@@ -0,0 +1,9 @@
+#![no_main]
+
+#[mock::app]
+mod app {
+    #[task(priority = PRIO - 1)]
+    fn foo(_: foo::Context) {}
+
+    const PRIO: u8 = 1;
+}
@@ -0,0 +1,7 @@
+#![no_main]
+
+#[mock::app]
+mod app {
+    #[task(wcet = 120)]
+    fn foo(_: foo::Context) {}
+}